@@ -0,0 +1,15 @@
+use crate::Error;
+use futures::future::BoxFuture;
+
+/// Provides the value of the `Authorization` header of every request the `Client` sends, such as an OAuth2 bearer token.
+///
+/// When the server rejects a request with `401 Unauthorized`, the `Client` calls `refresh` and sends the request once more with the new value. If that request is rejected as well, it fails with `Error::Unauthorized`.
+///
+/// Uploads may run in parallel, so `refresh` may be called by several requests at the same time.
+pub trait TokenProvider: Send + Sync {
+    /// Returns the current value of the `Authorization` header, such as `Bearer <token>`.
+    fn authorization(&self) -> BoxFuture<'_, Result<String, Error>>;
+
+    /// Obtains new credentials after the server rejected the current ones, returning the new value of the `Authorization` header.
+    fn refresh(&self) -> BoxFuture<'_, Result<String, Error>>;
+}
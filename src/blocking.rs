@@ -0,0 +1,124 @@
+use crate::http::{HttpHandler as AsyncHttpHandler, HttpRequest, HttpResponse};
+use crate::{ClientBuilder, Error, Metadata, ReaderSource, ServerInfo, UploadInfo, UploadResult};
+use futures::executor::block_on;
+use futures::io::AllowStdIo;
+use std::io::{Read, Seek};
+
+/// The trait used by `blocking::Client` to execute `HttpRequest`s, blocking the current thread until the response is received.
+pub trait HttpHandler {
+    fn handle_request(&self, req: HttpRequest<'_>) -> Result<HttpResponse, Error>;
+}
+
+/// A synchronous version of `tus_client::Client`, for code which doesn't use an async runtime.
+///
+/// Every method blocks the current thread until it is done. It must not be called from an async context.
+pub struct Client<H: HttpHandler> {
+    inner: crate::Client<SyncHandler<H>>,
+}
+
+impl<H> Clone for Client<H>
+where
+    H: HttpHandler,
+{
+    fn clone(&self) -> Self {
+        Client {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<H> Client<H>
+where
+    H: HttpHandler,
+{
+    /// Instantiates a new instance of `Client`. `http_handler` needs to implement the `blocking::HttpHandler` trait.
+    /// A default implementation of this trait for the `ureq` library is available by enabling the `ureq` feature.
+    pub fn new(http_handler: H) -> Self {
+        ClientBuilder::new().build_blocking(http_handler)
+    }
+
+    pub(crate) fn from_async(inner: crate::Client<SyncHandler<H>>) -> Self {
+        Client { inner }
+    }
+
+    /// Get info about a file on the server.
+    pub fn get_info(&self, url: &str) -> Result<UploadInfo, Error> {
+        block_on(self.inner.get_info(url))
+    }
+
+    /// Upload a file to the specified upload URL.
+    pub fn upload<R>(&self, url: &str, reader: R) -> Result<UploadResult, Error>
+    where
+        R: Read + Seek + Send,
+    {
+        self.upload_with_chunk_size(url, reader, self.inner.chunk_size())
+    }
+
+    /// Upload a file to the specified upload URL with the given chunk size.
+    pub fn upload_with_chunk_size<R>(
+        &self,
+        url: &str,
+        reader: R,
+        chunk_size: usize,
+    ) -> Result<UploadResult, Error>
+    where
+        R: Read + Seek + Send,
+    {
+        block_on(async {
+            let source = ReaderSource::new(AllowStdIo::new(reader)).await?;
+            self.inner
+                .upload_with_chunk_size(url, source, chunk_size)
+                .await
+        })
+    }
+
+    /// Get information about the tus server
+    pub fn get_server_info(&self, url: &str) -> Result<ServerInfo, Error> {
+        block_on(self.inner.get_server_info(url))
+    }
+
+    /// Create a file on the server, receiving the upload URL of the file.
+    pub fn create(&self, url: &str, len: u64) -> Result<String, Error> {
+        block_on(self.inner.create(url, len))
+    }
+
+    /// Create a file on the server including the specified metadata, receiving the upload URL of the file.
+    pub fn create_with_metadata(
+        &self,
+        url: &str,
+        len: u64,
+        metadata: Metadata,
+    ) -> Result<String, Error> {
+        block_on(self.inner.create_with_metadata(url, len, metadata))
+    }
+
+    /// Delete a file on the server.
+    pub fn delete(&self, url: &str) -> Result<(), Error> {
+        block_on(self.inner.delete(url))
+    }
+}
+
+impl ClientBuilder {
+    /// Creates a `blocking::Client`, which sends its requests through the synchronous `http_handler`.
+    ///
+    /// The `request_timeout` is ignored, since a blocking handler doesn't return before its request is done. Use the timeouts of the handler instead, such as `ureq::AgentBuilder::timeout`.
+    pub fn build_blocking<H>(mut self, http_handler: H) -> Client<H>
+    where
+        H: HttpHandler,
+    {
+        self.config.request_timeout = None;
+        Client::from_async(self.build(SyncHandler(http_handler)))
+    }
+}
+
+/// Runs a synchronous handler as part of the `Client`'s futures.
+pub(crate) struct SyncHandler<H>(H);
+
+impl<H> AsyncHttpHandler for SyncHandler<H>
+where
+    H: HttpHandler,
+{
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        self.0.handle_request(req)
+    }
+}
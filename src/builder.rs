@@ -0,0 +1,228 @@
+use crate::checksum::CHECKSUM_PREFERENCE;
+use crate::http::{Headers, HttpHandler, HttpMethod};
+use crate::{
+    ChecksumAlgorithm, Client, ProgressListener, RetryPolicy, ThreadTimer, Timer, TokenProvider,
+    DEFAULT_CHUNK_SIZE, MAX_OFFSET_RESYNCS,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The version of the tus protocol sent in the `Tus-Resumable` header by default.
+const DEFAULT_TUS_VERSION: &str = "1.0.0";
+
+/// Describes which requests are sent as `POST` requests, with the actual method in the `X-HTTP-Method-Override` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MethodOverride {
+    /// Every request is sent with its own method.
+    Never,
+    /// `PATCH` and `DELETE` requests are sent as `POST` requests, since those are the methods some environments don't support.
+    PatchAndDelete,
+    /// Every request is sent as a `POST` request.
+    Always,
+}
+
+impl MethodOverride {
+    /// Whether a request with `method` should be sent as a `POST` request.
+    pub(crate) fn applies_to(&self, method: HttpMethod) -> bool {
+        match self {
+            MethodOverride::Never => false,
+            MethodOverride::PatchAndDelete => {
+                matches!(method, HttpMethod::Patch | HttpMethod::Delete)
+            }
+            MethodOverride::Always => true,
+        }
+    }
+}
+
+/// Used to configure and create a `Client`.
+///
+/// ```rust,ignore
+/// let client = ClientBuilder::new()
+///     .chunk_size(1024 * 1024)
+///     .header("Authorization", "Bearer secret")
+///     .retry_policy(RetryPolicy::new())
+///     .request_timeout(Duration::from_secs(30))
+///     .build(reqwest::Client::new());
+/// ```
+#[derive(Clone)]
+pub struct ClientBuilder {
+    pub(crate) config: Config,
+}
+
+impl ClientBuilder {
+    /// Creates a builder with the default settings: 5 MiB chunks, tus version 1.0.0, no method override, no retries and no timeout.
+    pub fn new() -> Self {
+        ClientBuilder {
+            config: Config::default(),
+        }
+    }
+
+    /// Sets the size of the chunks files are uploaded in, unless a chunk size is passed to the upload method.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.config.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Adds a header which is sent with every request. Headers set by the `Client` itself take precedence.
+    pub fn header<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.config.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Adds headers which are sent with every request. Headers set by the `Client` itself take precedence.
+    pub fn headers(mut self, headers: Headers) -> Self {
+        self.config.headers.extend(headers);
+        self
+    }
+
+    /// Sets the version of the tus protocol sent in the `Tus-Resumable` header.
+    pub fn tus_version<V>(mut self, version: V) -> Self
+    where
+        V: Into<String>,
+    {
+        self.config.tus_version = version.into();
+        self
+    }
+
+    /// Sets which requests use the `X-HTTP-Method-Override` header, for environments which don't support all HTTP methods.
+    pub fn method_override(mut self, method_override: MethodOverride) -> Self {
+        self.config.method_override = method_override;
+        self
+    }
+
+    /// Sets the policy used to retry failed uploads. By default, failed requests are not retried.
+    ///
+    /// When uploading a chunk fails with a retryable error, the `Client` waits on its `timer`, asks the server for the current upload offset and continues the upload from there.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.config.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets how many times in a row the offset of an upload is requested from the server, after the server rejected a chunk with `409 Conflict` because of its offset. The upload continues from the offset the server reports.
+    ///
+    /// This happens when another client uploads to the same upload, or a request is replayed. Defaults to 3. With 0, such a chunk fails the upload with `Error::WrongUploadOffsetError`.
+    pub fn max_offset_resyncs(mut self, max_offset_resyncs: usize) -> Self {
+        self.config.max_offset_resyncs = max_offset_resyncs;
+        self
+    }
+
+    /// Sets whether the next chunk is read from the source while the current chunk is sent, so reading and sending overlap. By default, chunks are streamed from the source as they are sent.
+    ///
+    /// This helps with sources which are slow to read, such as network shares, but needs memory for two chunks per upload, or three with handlers which copy the chunk into their own request body, like the `reqwest` handler.
+    ///
+    /// The next chunk is read in the same task which sends the current chunk, once the request is waiting on the handler. Reading only overlaps with sending if the handler makes progress without being polled, like a `reqwest` or `hyper` client whose connections are driven by the runtime. A source whose `read_at` blocks blocks the executor thread while it reads, so use a source which reads without blocking, such as `File`, which reads on a separate thread, or a `ReaderSource` over an async file.
+    pub fn read_ahead(mut self, read_ahead: bool) -> Self {
+        self.config.read_ahead = read_ahead;
+        self
+    }
+
+    /// Sets whether uploads which expired are created again. By default, an expired upload fails with `Error::Gone`.
+    ///
+    /// This applies to `create_and_upload` and `upload_with_store`, which know where to create the file and with which metadata. The file is created again with the same metadata, and uploaded from the start.
+    pub fn recreate_expired_uploads(mut self, recreate: bool) -> Self {
+        self.config.recreate_expired_uploads = recreate;
+        self
+    }
+
+    /// Sets how long a single request may take, before it fails with `Error::Timeout`. By default, requests don't time out.
+    ///
+    /// This has no effect on a `blocking::Client`, whose handler has to time out requests itself.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.config.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timer used to time out requests and to wait between retries. By default, this is `ThreadTimer`, whose single thread serves the sleeps of all clients.
+    pub fn timer<T>(mut self, timer: T) -> Self
+    where
+        T: Timer + 'static,
+    {
+        self.config.timer = Arc::new(timer);
+        self
+    }
+
+    /// Sets the checksum algorithms to use, in order of preference. The first one the server supports is used. Pass an empty list to upload without checksums.
+    pub fn checksum_preference(mut self, algorithms: Vec<ChecksumAlgorithm>) -> Self {
+        self.config.checksum_preference = algorithms;
+        self
+    }
+
+    /// Sets a listener which receives the progress of every upload, unless a listener is passed to the upload method.
+    pub fn progress_listener<L>(mut self, listener: L) -> Self
+    where
+        L: ProgressListener + 'static,
+    {
+        self.config.progress_listener = Some(Arc::new(listener));
+        self
+    }
+
+    /// Sets the provider of the `Authorization` header of every request. The credentials are refreshed once when the server rejects them.
+    pub fn token_provider<P>(mut self, token_provider: P) -> Self
+    where
+        P: TokenProvider + 'static,
+    {
+        self.config.token_provider = Some(Arc::new(token_provider));
+        self
+    }
+
+    /// Creates the `Client`, which sends its requests through `http_handler`.
+    pub fn build<H>(self, http_handler: H) -> Client<H>
+    where
+        H: HttpHandler,
+    {
+        Client {
+            config: Arc::new(self.config),
+            http_handler: Arc::new(http_handler),
+            server_info_cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        ClientBuilder::new()
+    }
+}
+
+/// The settings of a `Client`, shared by its clones.
+#[derive(Clone)]
+pub(crate) struct Config {
+    pub(crate) chunk_size: usize,
+    pub(crate) headers: Headers,
+    pub(crate) tus_version: String,
+    pub(crate) method_override: MethodOverride,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) max_offset_resyncs: usize,
+    pub(crate) recreate_expired_uploads: bool,
+    pub(crate) read_ahead: bool,
+    pub(crate) request_timeout: Option<Duration>,
+    pub(crate) timer: Arc<dyn Timer>,
+    pub(crate) checksum_preference: Vec<ChecksumAlgorithm>,
+    pub(crate) progress_listener: Option<Arc<dyn ProgressListener>>,
+    pub(crate) token_provider: Option<Arc<dyn TokenProvider>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            headers: Headers::new(),
+            tus_version: DEFAULT_TUS_VERSION.to_owned(),
+            method_override: MethodOverride::Never,
+            retry_policy: RetryPolicy::none(),
+            max_offset_resyncs: MAX_OFFSET_RESYNCS,
+            recreate_expired_uploads: false,
+            read_ahead: false,
+            request_timeout: None,
+            timer: Arc::new(ThreadTimer),
+            checksum_preference: CHECKSUM_PREFERENCE.to_vec(),
+            progress_listener: None,
+            token_provider: None,
+        }
+    }
+}
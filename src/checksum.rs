@@ -0,0 +1,123 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use md5::Md5;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use std::fmt;
+use std::str::FromStr;
+
+/// The order in which checksum algorithms are picked, when the server supports more than one.
+pub(crate) const CHECKSUM_PREFERENCE: [ChecksumAlgorithm; 4] = [
+    ChecksumAlgorithm::Sha256,
+    ChecksumAlgorithm::Sha1,
+    ChecksumAlgorithm::Md5,
+    ChecksumAlgorithm::Crc32,
+];
+
+/// Enumerates the checksum algorithms the `Client` can use to verify uploaded chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Sha1,
+    Md5,
+    Sha256,
+    Crc32,
+}
+
+impl ChecksumAlgorithm {
+    /// The name of the algorithm, as used in the `Tus-Checksum-Algorithm` and `Upload-Checksum` headers.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha1 => "sha1",
+            ChecksumAlgorithm::Md5 => "md5",
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Crc32 => "crc32",
+        }
+    }
+
+    /// Calculates the checksum of `data`.
+    pub fn checksum(&self, data: &[u8]) -> Vec<u8> {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    /// Creates the value of the `Upload-Checksum` header for `data`.
+    pub fn header_value(&self, data: &[u8]) -> String {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.header_value()
+    }
+
+    /// Creates a `Hasher`, which calculates the checksum of data that is read piece by piece.
+    pub(crate) fn hasher(&self) -> Hasher {
+        let state = match self {
+            ChecksumAlgorithm::Sha1 => HasherState::Sha1(Sha1::new()),
+            ChecksumAlgorithm::Md5 => HasherState::Md5(Md5::new()),
+            ChecksumAlgorithm::Sha256 => HasherState::Sha256(Sha256::new()),
+            ChecksumAlgorithm::Crc32 => HasherState::Crc32(crc32fast::Hasher::new()),
+        };
+        Hasher {
+            algorithm: *self,
+            state,
+        }
+    }
+}
+
+/// Calculates a checksum incrementally.
+pub(crate) struct Hasher {
+    algorithm: ChecksumAlgorithm,
+    state: HasherState,
+}
+
+enum HasherState {
+    Sha1(Sha1),
+    Md5(Md5),
+    Sha256(Sha256),
+    Crc32(crc32fast::Hasher),
+}
+
+impl Hasher {
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            HasherState::Sha1(hasher) => hasher.update(data),
+            HasherState::Md5(hasher) => hasher.update(data),
+            HasherState::Sha256(hasher) => hasher.update(data),
+            HasherState::Crc32(hasher) => hasher.update(data),
+        }
+    }
+
+    pub(crate) fn finalize(self) -> Vec<u8> {
+        match self.state {
+            HasherState::Sha1(hasher) => hasher.finalize().to_vec(),
+            HasherState::Md5(hasher) => hasher.finalize().to_vec(),
+            HasherState::Sha256(hasher) => hasher.finalize().to_vec(),
+            HasherState::Crc32(hasher) => hasher.finalize().to_be_bytes().to_vec(),
+        }
+    }
+
+    /// Creates the value of the `Upload-Checksum` header for the data passed so far.
+    pub(crate) fn header_value(self) -> String {
+        let name = self.algorithm.name();
+        format!("{} {}", name, STANDARD.encode(self.finalize()))
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ChecksumAlgorithm {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "sha1" => Ok(ChecksumAlgorithm::Sha1),
+            "md5" => Ok(ChecksumAlgorithm::Md5),
+            "sha256" => Ok(ChecksumAlgorithm::Sha256),
+            "crc32" => Ok(ChecksumAlgorithm::Crc32),
+            _ => Err(()),
+        }
+    }
+}
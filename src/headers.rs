@@ -1,3 +1,5 @@
+#![allow(clippy::redundant_static_lifetimes)]
+
 /// Indicates a byte offset withing a resource.
 pub const UPLOAD_OFFSET: &'static str = "upload-offset";
 
@@ -22,11 +24,26 @@ pub const X_HTTP_METHOD_OVERRIDE: &'static str = "x-http-method-override";
 /// Use this header if its environment does not support the PATCH or DELETE methods.
 pub const CONTENT_TYPE: &'static str = "content-type";
 
-/// Use this header if its environment does not support the PATCH or DELETE methods.
-//pub const UPLOAD_DEFER_LENGTH: &'static str = "upload-defer-length";
+/// Indicates that the size of the upload is not known currently and will be transferred later.
+pub const UPLOAD_DEFER_LENGTH: &'static str = "upload-defer-length";
 
 /// Use this header if its environment does not support the PATCH or DELETE methods.
 pub const UPLOAD_METADATA: &'static str = "upload-metadata";
 
 /// Use this header if its environment does not support the PATCH or DELETE methods.
 pub const LOCATION: &'static str = "location";
+
+/// A comma-separated list of the checksum algorithms supported by the server.
+pub const TUS_CHECKSUM_ALGORITHM: &'static str = "tus-checksum-algorithm";
+
+/// The checksum algorithm and the base64 encoded checksum of the request body.
+pub const UPLOAD_CHECKSUM: &'static str = "upload-checksum";
+
+/// Marks an upload as a partial upload, or as the final concatenation of partial uploads.
+pub const UPLOAD_CONCAT: &'static str = "upload-concat";
+
+/// The time after which an unfinished upload expires, formatted as an RFC 7231 datetime.
+pub const UPLOAD_EXPIRES: &'static str = "upload-expires";
+
+/// The credentials authenticating the request.
+pub const AUTHORIZATION: &'static str = "authorization";
@@ -1,12 +1,24 @@
 use crate::Error;
+use futures::future::BoxFuture;
+use futures::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
 use std::collections::HashMap;
 use std::fmt;
+use std::future::Future;
+use std::io::SeekFrom;
+
+mod layer;
+
+pub use self::layer::{
+    BearerAuthLayer, HeadersLayer, HttpHandlerExt, Layer, Logging, LoggingLayer, Stack, WithHeaders,
+};
+#[cfg(feature = "tower")]
+pub use crate::tower::ServiceHandler;
 
 /// An alias for `HashMap<String, String>`, which represents a set of HTTP headers and their values.
 pub type Headers = HashMap<String, String>;
 
 /// Enumerates the HTTP methods used by `tus_client::Client`.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HttpMethod {
     Head,
     Patch,
@@ -27,7 +39,95 @@ pub struct HttpRequest<'a> {
     pub method: HttpMethod,
     pub headers: Headers,
     pub url: String,
-    pub body: Option<&'a [u8]>,
+    pub body: Option<HttpBody<'a>>,
+}
+
+impl HttpRequest<'_> {
+    /// Borrows the request, so it can be sent again once the returned request is done.
+    pub(crate) fn reborrow(&mut self) -> HttpRequest<'_> {
+        HttpRequest {
+            method: self.method,
+            headers: self.headers.clone(),
+            url: self.url.clone(),
+            body: self.body.as_mut().map(HttpBody::reborrow),
+        }
+    }
+}
+
+/// A reader which the body of a request is streamed from.
+///
+/// It is implemented for every reader which is `AsyncRead + AsyncSeek + Send + Unpin`. Handlers only need to read from it.
+pub trait BodyReader: AsyncRead + AsyncSeek + Send + Unpin {}
+
+impl<R> BodyReader for R where R: AsyncRead + AsyncSeek + Send + Unpin {}
+
+/// The body of an `HttpRequest`.
+pub enum HttpBody<'a> {
+    /// A body which is in memory already.
+    Bytes(&'a [u8]),
+    /// A body which is read while the request is sent, so chunks are streamed from their source instead of being buffered in memory.
+    ///
+    /// The reader returns exactly `len` bytes, and fails with an `io::ErrorKind::UnexpectedEof` error if the source is shorter than that.
+    Reader {
+        reader: &'a mut dyn BodyReader,
+        len: u64,
+    },
+}
+
+impl HttpBody<'_> {
+    /// The length of the body in bytes.
+    pub fn len(&self) -> u64 {
+        match self {
+            HttpBody::Bytes(bytes) => bytes.len() as u64,
+            HttpBody::Reader { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the whole body into memory, for handlers which can't stream it.
+    pub async fn into_vec(self) -> Result<Vec<u8>, Error> {
+        match self {
+            HttpBody::Bytes(bytes) => Ok(bytes.to_vec()),
+            HttpBody::Reader { reader, len } => {
+                let mut buffer = Vec::with_capacity(len as usize);
+                reader.take(len).read_to_end(&mut buffer).await?;
+                Ok(buffer)
+            }
+        }
+    }
+
+    pub(crate) fn reborrow(&mut self) -> HttpBody<'_> {
+        match self {
+            HttpBody::Bytes(bytes) => HttpBody::Bytes(bytes),
+            HttpBody::Reader { reader, len } => HttpBody::Reader {
+                reader: &mut **reader,
+                len: *len,
+            },
+        }
+    }
+
+    /// Moves a streamed body back to its start, so it can be sent again.
+    pub(crate) async fn rewind(&mut self) -> Result<(), Error> {
+        if let HttpBody::Reader { reader, .. } = self {
+            reader.seek(SeekFrom::Start(0)).await?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for HttpBody<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HttpBody::Bytes(bytes) => f.debug_tuple("Bytes").field(bytes).finish(),
+            HttpBody::Reader { len, .. } => f
+                .debug_struct("Reader")
+                .field("len", len)
+                .finish_non_exhaustive(),
+        }
+    }
 }
 
 /// Represents an HTTP response from the server.
@@ -38,11 +138,80 @@ pub struct HttpResponse {
 }
 
 /// The required trait used by `tus_client::Client` to represent a handler to execute `HttpRequest`s.
+///
+/// Handlers don't need to be `Send`, so handlers built on `Rc` or on the `fetch` API of a browser work as well. Implement `SendHttpHandler` instead for handlers whose futures are `Send`.
 pub trait HttpHandler {
     #[allow(async_fn_in_trait)]
     async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error>;
 }
 
+/// A version of `HttpHandler` whose futures are `Send`, so the futures of a `Client` using it are `Send` as well, and can be spawned on a multi-threaded runtime.
+///
+/// Every `SendHttpHandler` is an `HttpHandler`. Only a `SendHttpHandler` can be wrapped in `Layer`s or boxed as a `DynHttpHandler`. Implementations can use an `async fn`:
+///
+/// ```rust,ignore
+/// impl SendHttpHandler for MyHandler {
+///     async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+///         // ...
+///     }
+/// }
+/// ```
+pub trait SendHttpHandler: Send + Sync {
+    fn handle_request<'a>(
+        &self,
+        req: HttpRequest<'a>,
+    ) -> impl Future<Output = Result<HttpResponse, Error>> + Send;
+}
+
+impl<H> HttpHandler for H
+where
+    H: SendHttpHandler,
+{
+    fn handle_request<'a>(
+        &self,
+        req: HttpRequest<'a>,
+    ) -> impl Future<Output = Result<HttpResponse, Error>> {
+        SendHttpHandler::handle_request(self, req)
+    }
+}
+
+/// An object-safe version of `SendHttpHandler`, for choosing the handler at runtime.
+///
+/// Every `SendHttpHandler` is a `DynHttpHandler`, and a `Box<dyn DynHttpHandler>` is a `SendHttpHandler`, so a `Client<Box<dyn DynHttpHandler>>` can use any handler:
+///
+/// ```rust,ignore
+/// let handler: Box<dyn DynHttpHandler> = if use_proxy {
+///     Box::new(proxy_handler)
+/// } else {
+///     Box::new(reqwest::Client::new())
+/// };
+/// let client = Client::new(handler);
+/// ```
+pub trait DynHttpHandler: Send + Sync {
+    fn handle_request_boxed<'h, 'a: 'h>(
+        &'h self,
+        req: HttpRequest<'a>,
+    ) -> BoxFuture<'h, Result<HttpResponse, Error>>;
+}
+
+impl<H> DynHttpHandler for H
+where
+    H: SendHttpHandler,
+{
+    fn handle_request_boxed<'h, 'a: 'h>(
+        &'h self,
+        req: HttpRequest<'a>,
+    ) -> BoxFuture<'h, Result<HttpResponse, Error>> {
+        Box::pin(SendHttpHandler::handle_request(self, req))
+    }
+}
+
+impl SendHttpHandler for Box<dyn DynHttpHandler> {
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        self.as_ref().handle_request_boxed(req).await
+    }
+}
+
 /// Returns the default headers required to make requests to an tus enabled endpoint.
 pub fn default_headers() -> Headers {
     let mut map = Headers::new();
@@ -0,0 +1,209 @@
+use crate::http::{Headers, HttpBody, HttpRequest, HttpResponse, SendHttpHandler};
+use crate::Error;
+use std::time::Instant;
+
+/// Wraps a `SendHttpHandler` in another `SendHttpHandler`, which can inspect or change every request before passing it on, and every response before returning it.
+///
+/// Layers are applied with `HttpHandlerExt::layer`. The layer applied last sees a request first:
+///
+/// ```rust,ignore
+/// let handler = reqwest::Client::new()
+///     .layer(LoggingLayer::new())
+///     .layer(BearerAuthLayer::new("secret"));
+/// let client = Client::new(handler);
+/// ```
+pub trait Layer<H> {
+    /// The handler wrapping `H`.
+    type Handler: SendHttpHandler;
+
+    /// Wraps `inner` in the handler of this layer.
+    fn layer(&self, inner: H) -> Self::Handler;
+}
+
+/// Applies `Layer`s to a `SendHttpHandler`. Implemented for every `SendHttpHandler`.
+pub trait HttpHandlerExt: SendHttpHandler + Sized {
+    /// Wraps this handler in `layer`.
+    fn layer<L>(self, layer: L) -> L::Handler
+    where
+        L: Layer<Self>,
+    {
+        layer.layer(self)
+    }
+}
+
+impl<H> HttpHandlerExt for H where H: SendHttpHandler {}
+
+/// Combines two layers into one, applying `inner` first and `outer` around it.
+#[derive(Debug, Clone)]
+pub struct Stack<Inner, Outer> {
+    inner: Inner,
+    outer: Outer,
+}
+
+impl<Inner, Outer> Stack<Inner, Outer> {
+    pub fn new(inner: Inner, outer: Outer) -> Self {
+        Stack { inner, outer }
+    }
+}
+
+impl<H, Inner, Outer> Layer<H> for Stack<Inner, Outer>
+where
+    Inner: Layer<H>,
+    Outer: Layer<Inner::Handler>,
+{
+    type Handler = Outer::Handler;
+
+    fn layer(&self, inner: H) -> Self::Handler {
+        self.outer.layer(self.inner.layer(inner))
+    }
+}
+
+/// A layer which adds the same headers to every request, unless the request already has a header with the same name.
+#[derive(Debug, Clone, Default)]
+pub struct HeadersLayer {
+    headers: Headers,
+}
+
+impl HeadersLayer {
+    pub fn new(headers: Headers) -> Self {
+        HeadersLayer { headers }
+    }
+
+    /// Adds a header to the headers added to every request.
+    pub fn header<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+}
+
+impl<H> Layer<H> for HeadersLayer
+where
+    H: SendHttpHandler,
+{
+    type Handler = WithHeaders<H>;
+
+    fn layer(&self, inner: H) -> Self::Handler {
+        WithHeaders {
+            inner,
+            headers: self.headers.clone(),
+        }
+    }
+}
+
+/// The handler of `HeadersLayer` and `BearerAuthLayer`.
+#[derive(Debug, Clone)]
+pub struct WithHeaders<H> {
+    inner: H,
+    headers: Headers,
+}
+
+impl<H> SendHttpHandler for WithHeaders<H>
+where
+    H: SendHttpHandler,
+{
+    async fn handle_request<'a>(&self, mut req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        for (name, value) in &self.headers {
+            if !req.headers.keys().any(|key| key.eq_ignore_ascii_case(name)) {
+                req.headers.insert(name.clone(), value.clone());
+            }
+        }
+
+        SendHttpHandler::handle_request(&self.inner, req).await
+    }
+}
+
+/// A layer which authenticates every request with a bearer token in the `Authorization` header.
+#[derive(Debug, Clone)]
+pub struct BearerAuthLayer {
+    token: String,
+}
+
+impl BearerAuthLayer {
+    pub fn new<T>(token: T) -> Self
+    where
+        T: Into<String>,
+    {
+        BearerAuthLayer {
+            token: token.into(),
+        }
+    }
+}
+
+impl<H> Layer<H> for BearerAuthLayer
+where
+    H: SendHttpHandler,
+{
+    type Handler = WithHeaders<H>;
+
+    fn layer(&self, inner: H) -> Self::Handler {
+        HeadersLayer::default()
+            .header("Authorization", format!("Bearer {}", self.token))
+            .layer(inner)
+    }
+}
+
+/// A layer which logs every request and its outcome through the `log` crate.
+///
+/// Requests and responses are logged at the `debug` level, errors at the `warn` level. Header values are never logged, since they may contain credentials.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingLayer;
+
+impl LoggingLayer {
+    pub fn new() -> Self {
+        LoggingLayer
+    }
+}
+
+impl<H> Layer<H> for LoggingLayer
+where
+    H: SendHttpHandler,
+{
+    type Handler = Logging<H>;
+
+    fn layer(&self, inner: H) -> Self::Handler {
+        Logging { inner }
+    }
+}
+
+/// The handler of `LoggingLayer`.
+#[derive(Debug, Clone)]
+pub struct Logging<H> {
+    inner: H,
+}
+
+impl<H> SendHttpHandler for Logging<H>
+where
+    H: SendHttpHandler,
+{
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        let method = req.method;
+        let url = req.url.clone();
+        let body_len = req.body.as_ref().map_or(0, HttpBody::len);
+        log::debug!("{} {} ({} bytes)", method, url, body_len);
+
+        let started = Instant::now();
+        let result = SendHttpHandler::handle_request(&self.inner, req).await;
+        match &result {
+            Ok(response) => log::debug!(
+                "{} {} -> {} in {:?}",
+                method,
+                url,
+                response.status_code,
+                started.elapsed()
+            ),
+            Err(err) => log::warn!(
+                "{} {} failed after {:?}: {}",
+                method,
+                url,
+                started.elapsed(),
+                err
+            ),
+        }
+
+        result
+    }
+}
@@ -12,9 +12,15 @@
 //! tus_client = {version = "x.x.x", features = ["reqwest"]}
 //! ```
 //!
+//! Handlers which implement `SendHttpHandler` are `Send + Sync` and return `Send` futures, so the futures of a `Client` using them are `Send` as well and can be spawned on a multi-threaded runtime, such as with `tokio::spawn`. The included handlers all implement it. Handlers which aren't `Send`, such as handlers built on `Rc`, implement `HttpHandler` instead.
+//!
+//! Chunks are streamed from the `UploadSource` to the handler, through the `HttpBody` of each request, instead of being read into memory first.
+//!
+//! To use the blocking `reqwest::blocking::Client` as a handler instead, specify the `reqwest-blocking` feature. The blocking handler blocks the thread executing the upload until each response is received, so it should not be used on an async runtime.
+//!
 //! ## Usage
 //!
-//! ```rust
+//! ```rust,ignore
 //! use tus_client::Client;
 //! use reqwest;
 //!
@@ -44,31 +50,86 @@
 //!
 //! `upload` (and `upload_with_chunk_size`) will automatically resume the upload from where it left off, if the upload transfer is interrupted.
 #![doc(html_root_url = "https://docs.rs/tus_client/0.1.1")]
-use crate::http::{default_headers, Headers, HttpHandler, HttpMethod, HttpRequest};
-use base64::engine::general_purpose::STANDARD;
-use base64::Engine;
-use futures::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+pub use crate::auth::TokenProvider;
+use crate::builder::Config;
+pub use crate::builder::{ClientBuilder, MethodOverride};
+pub use crate::checksum::ChecksumAlgorithm;
+use crate::http::{Headers, HttpBody, HttpHandler, HttpMethod, HttpRequest, HttpResponse};
+pub use crate::metadata::Metadata;
+pub use crate::progress::{
+    progress_channel, ProgressEvent, ProgressListener, ProgressSender, ProgressStream,
+};
+use crate::progress::{PartsProgress, ProgressTracker};
+pub use crate::retry::{RetryPolicy, ThreadTimer, Timer};
+use crate::source::{read_exact_at, ChunkReader};
+pub use crate::source::{ReaderSource, SourceRange, UploadSource};
+pub use crate::store::{Fingerprint, JsonFileStore, StoredUpload, UploadStore};
+use crate::upload::UploadControl;
+pub use crate::upload::{Upload, UploadHandle, UploadState};
+use futures::future::{join, select, try_join_all, Either};
+use futures::pin_mut;
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt};
 use std::collections::HashMap;
 use std::error::Error as StdError;
 use std::fmt::{Display, Formatter};
 use std::io;
 use std::io::SeekFrom;
+use std::mem;
 use std::num::ParseIntError;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
+mod auth;
+/// Contains a synchronous `Client`, for code which doesn't use an async runtime.
+pub mod blocking;
+mod builder;
+mod checksum;
 mod headers;
 /// Contains the `HttpHandler` trait and related structs. This module is only relevant when implement `HttpHandler` manually.
 pub mod http;
+mod metadata;
+mod progress;
+mod retry;
+mod source;
+mod store;
+/// Contains an in-memory tus server, for testing code which uses `Client`. Enable the `testing` feature to use this module.
+#[cfg(feature = "testing")]
+pub mod testing;
+mod upload;
 
 #[cfg(feature = "reqwest")]
 mod reqwest;
+#[cfg(feature = "tower")]
+mod tower;
+#[cfg(feature = "ureq")]
+mod ureq;
 
 const DEFAULT_CHUNK_SIZE: usize = 5 * 1024 * 1024;
+const MAX_CHECKSUM_ATTEMPTS: usize = 3;
+const MAX_EXPIRED_RECREATIONS: usize = 3;
+const MAX_OFFSET_RESYNCS: usize = 3;
 
 /// Used to interact with a [tus](https://tus.io) endpoint.
+///
+/// Cloning a `Client` is cheap: clones share the HTTP handler, the settings and the cached server information, so a `Client` can be cloned for every task using it.
 pub struct Client<H: HttpHandler> {
-    use_method_override: bool,
-    http_handler: H,
+    config: Arc<Config>,
+    http_handler: Arc<H>,
+    server_info_cache: Arc<Mutex<HashMap<String, ServerInfo>>>,
+}
+
+impl<H> Clone for Client<H>
+where
+    H: HttpHandler,
+{
+    fn clone(&self) -> Self {
+        Client {
+            config: self.config.clone(),
+            http_handler: self.http_handler.clone(),
+            server_info_cache: self.server_info_cache.clone(),
+        }
+    }
 }
 
 impl<H> Client<H>
@@ -78,143 +139,372 @@ where
     /// Instantiates a new instance of `Client`. `http_handler` needs to implement the `HttpHandler` trait.
     /// A default implementation of this trait for the `reqwest` library is available by enabling the `reqwest` feature.
     pub fn new(http_handler: H) -> Self {
-        Client {
-            use_method_override: false,
-            http_handler,
-        }
+        ClientBuilder::new().build(http_handler)
     }
 
     /// Some environments might not support using the HTTP methods `PATCH` and `DELETE`. Use this method to create a `Client` which uses the `X-HTTP-METHOD-OVERRIDE` header to specify these methods instead.
     pub fn with_method_override(http_handler: H) -> Self {
-        Client {
-            use_method_override: true,
-            http_handler,
-        }
+        ClientBuilder::new()
+            .method_override(MethodOverride::Always)
+            .build(http_handler)
+    }
+
+    /// The size of the chunks files are uploaded in, unless a chunk size is passed to the upload method.
+    pub fn chunk_size(&self) -> usize {
+        self.config.chunk_size
     }
 
     /// Get info about a file on the server.
     pub async fn get_info(&self, url: &str) -> Result<UploadInfo, Error> {
-        let req = self.create_request(HttpMethod::Head, url, None, Some(default_headers()));
+        let req = self.create_request(HttpMethod::Head, url, None, None);
 
-        let response = self.http_handler.handle_request(req).await?;
+        let response = self.send(req).await?;
+
+        if [401, 410, 423].contains(&response.status_code) {
+            return Err(status_error(response.status_code));
+        }
 
         let bytes_uploaded = response.headers.get_by_key(headers::UPLOAD_OFFSET);
+        if response.status_code.to_string().starts_with('4') || bytes_uploaded.is_none() {
+            if response.status_code >= 500 {
+                return Err(Error::UnexpectedStatusCode(response.status_code));
+            }
+            return Err(Error::NotFoundError);
+        }
+
+        let bytes_uploaded = parse_size(headers::UPLOAD_OFFSET, bytes_uploaded.unwrap())?;
         let total_size = response
             .headers
             .get_by_key(headers::UPLOAD_LENGTH)
-            .and_then(|l| l.parse::<usize>().ok());
+            .map(|length| parse_size(headers::UPLOAD_LENGTH, length))
+            .transpose()?;
         let metadata = response
             .headers
             .get_by_key(headers::UPLOAD_METADATA)
-            .and_then(|data| STANDARD.decode(data).ok())
-            .map(|decoded| {
-                String::from_utf8(decoded).unwrap().split(';').fold(
-                    HashMap::new(),
-                    |mut acc, key_val| {
-                        let mut parts = key_val.splitn(2, ':');
-                        if let Some(key) = parts.next() {
-                            acc.insert(
-                                String::from(key),
-                                String::from(parts.next().unwrap_or_default()),
-                            );
-                        }
-                        acc
-                    },
-                )
-            });
-
-        if response.status_code.to_string().starts_with('4') || bytes_uploaded.is_none() {
-            return Err(Error::NotFoundError);
-        }
-
-        let bytes_uploaded = bytes_uploaded.unwrap().parse()?;
+            .map(|metadata| Metadata::from_header(metadata));
 
         Ok(UploadInfo {
             bytes_uploaded,
             total_size,
             metadata,
+            expires: parse_expires(&response.headers)?,
         })
     }
 
     /// Upload a file to the specified upload URL.
-    pub async fn upload<R>(&self, url: &str, reader: R) -> Result<(), Error>
+    pub async fn upload<U>(&self, url: &str, source: U) -> Result<UploadResult, Error>
     where
-        R: AsyncRead + AsyncSeek + Unpin,
+        U: UploadSource,
     {
-        self.upload_with_chunk_size(url, reader, DEFAULT_CHUNK_SIZE)
+        self.upload_with_chunk_size(url, source, self.config.chunk_size)
             .await
     }
 
-    /// Upload a file to the specified upload URL with the given chunk size.
-    pub async fn upload_with_chunk_size<R>(
+    /// Upload a file to the specified upload URL with the given chunk size. A chunk size of 0 is treated as 1, like in `ClientBuilder::chunk_size`.
+    ///
+    /// If the server supports the checksum extension, every chunk is sent with an `Upload-Checksum` header. A chunk which is rejected because of a checksum mismatch is sent again.
+    pub async fn upload_with_chunk_size<U>(
         &self,
         url: &str,
-        mut reader: R,
+        source: U,
         chunk_size: usize,
-    ) -> Result<(), Error>
+    ) -> Result<UploadResult, Error>
+    where
+        U: UploadSource,
+    {
+        self.upload_with_listener(url, source, chunk_size, None, None)
+            .await
+    }
+
+    /// Upload a file to the specified upload URL with the given chunk size, reporting the progress of the upload to `listener`.
+    ///
+    /// Use `progress_channel` to receive the progress as a `Stream` instead.
+    pub async fn upload_with_progress<U>(
+        &self,
+        url: &str,
+        source: U,
+        chunk_size: usize,
+        listener: &dyn ProgressListener,
+    ) -> Result<UploadResult, Error>
+    where
+        U: UploadSource,
+    {
+        self.upload_with_listener(url, source, chunk_size, Some(listener), None)
+            .await
+    }
+
+    /// Prepare an upload of a file to the specified upload URL, which can be paused, resumed and cancelled while it is running.
+    ///
+    /// The upload starts once `Upload::run` is called. Use `Upload::handle` to get a handle to control it.
+    pub fn begin_upload<U>(&self, url: &str, source: U) -> Upload<'_, H, U>
+    where
+        U: UploadSource,
+    {
+        Upload::new(self, url, source)
+    }
+
+    pub(crate) async fn upload_with_listener<U>(
+        &self,
+        url: &str,
+        source: U,
+        chunk_size: usize,
+        listener: Option<&dyn ProgressListener>,
+        control: Option<&UploadControl>,
+    ) -> Result<UploadResult, Error>
     where
-        R: AsyncRead + AsyncSeek + Unpin,
+        U: UploadSource,
     {
         let info = self.get_info(url).await?;
-        let file_len = reader.seek(SeekFrom::End(0)).await? as usize;
+        let file_len = source.len()?;
 
         if let Some(total_size) = info.total_size {
-            if file_len as usize != total_size {
+            if file_len != total_size {
                 return Err(Error::UnequalSizeError);
             }
         }
 
-        let mut buffer = vec![0; chunk_size];
-        let mut progress = info.bytes_uploaded;
+        if let Some(control) = control {
+            control.started(&info);
+        }
 
-        loop {
-            reader.seek(SeekFrom::Start(progress as u64)).await?;
+        let params = UploadParams {
+            url,
+            chunk_size: chunk_size.max(1),
+            checksum_algorithm: self.negotiate_checksum_algorithm(url).await?,
+            progress: ProgressTracker::new(
+                listener.or(self.config.progress_listener.as_deref()),
+                info.bytes_uploaded,
+                Some(file_len),
+            ),
+            control,
+        };
 
-            // fill the chunk buffer until the end of the file is reached or the buffer is filled
-            let mut bytes_read = 0;
-            while bytes_read < buffer.len() {
-                let read = reader.read(&mut buffer[bytes_read..]).await?;
-                if read == 0 {
-                    break;
-                }
-                bytes_read += read;
-            }
+        self.upload_from(&params, &source, file_len, UploadPosition::from(info))
+            .await
+    }
+
+    /// Upload the remainder of a file, starting at the offset of `state`.
+    async fn upload_from<U>(
+        &self,
+        params: &UploadParams<'_>,
+        source: &U,
+        file_len: u64,
+        mut state: UploadPosition,
+    ) -> Result<UploadResult, Error>
+    where
+        U: UploadSource,
+    {
+        let mut attempts = Attempts::default();
+        let mut read_ahead = self.config.read_ahead.then(ReadAhead::default);
 
-            if bytes_read == 0 {
-                return Err(Error::FileReadError);
+        while state.offset < file_len {
+            if let Some(control) = params.control {
+                control.checkpoint().await?;
             }
 
-            // create a request with the chunk buffer and upload it
-            let req = self.create_request(
-                HttpMethod::Patch,
-                url,
-                Some(&buffer[..bytes_read]),
-                Some(create_upload_headers(progress)),
-            );
+            let chunk_len = (file_len - state.offset).min(params.chunk_size as u64);
 
-            let response = self.http_handler.handle_request(req).await?;
+            params
+                .progress
+                .chunk_started(state.offset, chunk_len as usize);
 
-            if response.status_code == 409 {
-                return Err(Error::WrongUploadOffsetError);
-            }
+            let result = match &mut read_ahead {
+                Some(read_ahead) => {
+                    self.upload_chunk_read_ahead(params, source, read_ahead, state.offset, file_len)
+                        .await
+                }
+                None => {
+                    // the chunk is streamed from the source, so it is never held in memory as a whole
+                    let mut chunk = ChunkReader::new(source, state.offset, chunk_len);
+                    self.upload_chunk(
+                        params.url,
+                        state.offset,
+                        HttpBody::Reader {
+                            reader: &mut chunk,
+                            len: chunk_len,
+                        },
+                        params.checksum_algorithm,
+                        None,
+                    )
+                    .await
+                }
+            };
 
-            if response.status_code == 404 {
-                return Err(Error::NotFoundError);
-            }
+            let confirmed = match result {
+                Ok(confirmed) => {
+                    attempts = Attempts::default();
+                    confirmed
+                }
+                Err(err) => self.resync_offset(params, err, &mut attempts).await?,
+            };
+            state = confirmed;
 
-            if response.status_code != 204 {
-                return Err(Error::UnexpectedStatusCode(response.status_code));
+            params.progress.confirmed(state.offset);
+            if let Some(control) = params.control {
+                control.confirmed(state.offset);
             }
+        }
 
-            let upload_offset = match response.headers.get_by_key(headers::UPLOAD_OFFSET) {
-                Some(offset) => Ok(offset),
-                None => Err(Error::MissingHeader(headers::UPLOAD_OFFSET.to_owned())),
-            }?;
+        params.progress.completed(state.offset);
 
-            progress = upload_offset.parse()?;
+        Ok(state.into_result(params.url))
+    }
 
-            if progress >= file_len as usize {
-                return Ok(());
+    /// Send the chunk at `offset` from memory, while the chunk after it is read into the other buffer of `read_ahead`.
+    async fn upload_chunk_read_ahead<U>(
+        &self,
+        params: &UploadParams<'_>,
+        source: &U,
+        read_ahead: &mut ReadAhead,
+        offset: u64,
+        file_len: u64,
+    ) -> Result<UploadPosition, Error>
+    where
+        U: UploadSource,
+    {
+        let chunk_len = (file_len - offset).min(params.chunk_size as u64) as usize;
+
+        // the chunk read ahead is only used if the upload continues where it was expected to, otherwise it is thrown away
+        if read_ahead.next_offset.take() == Some(offset) {
+            mem::swap(&mut read_ahead.current, &mut read_ahead.next);
+        } else {
+            read_ahead.current.resize(chunk_len, 0);
+            read_exact_at(source, offset, &mut read_ahead.current).await?;
+        }
+
+        let ReadAhead {
+            current,
+            next,
+            next_offset,
+        } = read_ahead;
+        let following = offset + chunk_len as u64;
+        let following_len = (file_len - following).min(params.chunk_size as u64) as usize;
+
+        let read_next = async {
+            next.resize(following_len, 0);
+            // a failed read isn't reported here, since the chunk is read again when it is sent
+            following_len > 0 && read_exact_at(source, following, next).await.is_ok()
+        };
+        let send = self.upload_chunk(
+            params.url,
+            offset,
+            HttpBody::Bytes(current),
+            params.checksum_algorithm,
+            None,
+        );
+
+        let (result, read) = join(send, read_next).await;
+        if read {
+            *next_offset = Some(following);
+        }
+
+        result
+    }
+
+    /// Upload a stream of unknown length to the specified upload URL.
+    ///
+    /// The upload needs to be created with `create_with_deferred_length`. The length of the upload is declared to the server once the end of the stream is reached.
+    pub async fn upload_stream<R>(&self, url: &str, reader: R) -> Result<UploadResult, Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.upload_stream_with_chunk_size(url, reader, self.config.chunk_size)
+            .await
+    }
+
+    /// Upload a stream of unknown length to the specified upload URL with the given chunk size. A chunk size of 0 is treated as 1, like in `ClientBuilder::chunk_size`.
+    ///
+    /// Since the stream can't be seeked, resuming an upload skips the bytes already uploaded by reading and discarding them from the stream.
+    pub async fn upload_stream_with_chunk_size<R>(
+        &self,
+        url: &str,
+        mut reader: R,
+        chunk_size: usize,
+    ) -> Result<UploadResult, Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let info = self.get_info(url).await?;
+        let total_size = info.total_size;
+        let mut state = UploadPosition::from(info);
+
+        if total_size.is_some_and(|total_size| state.offset >= total_size) {
+            return Ok(state.into_result(url));
+        }
+
+        let skipped =
+            futures::io::copy((&mut reader).take(state.offset), &mut futures::io::sink()).await?;
+        if skipped < state.offset {
+            return Err(Error::FileReadError);
+        }
+
+        let params = UploadParams {
+            url,
+            chunk_size,
+            checksum_algorithm: self.negotiate_checksum_algorithm(url).await?,
+            progress: ProgressTracker::new(
+                self.config.progress_listener.as_deref(),
+                state.offset,
+                None,
+            ),
+            control: None,
+        };
+
+        let mut buffer = vec![0; chunk_size.max(1)];
+        let mut attempts = Attempts::default();
+
+        loop {
+            let bytes_read = read_chunk(&mut reader, &mut buffer).await?;
+            let chunk_start = state.offset;
+            let chunk_end = chunk_start + bytes_read as u64;
+
+            // a partially filled buffer means the end of the stream was reached, so the length of the upload is known
+            let upload_length = if bytes_read < buffer.len() {
+                Some(chunk_end)
+            } else {
+                None
+            };
+
+            // the stream can't be read again, so a failed chunk is resumed from the buffer
+            loop {
+                // the offset is within the buffer, as checked below
+                let buffered = (state.offset - chunk_start) as usize;
+                params
+                    .progress
+                    .chunk_started(state.offset, bytes_read - buffered);
+
+                let confirmed = match self
+                    .upload_chunk(
+                        url,
+                        state.offset,
+                        HttpBody::Bytes(&buffer[buffered..bytes_read]),
+                        params.checksum_algorithm,
+                        upload_length,
+                    )
+                    .await
+                {
+                    Ok(confirmed) => {
+                        attempts = Attempts::default();
+                        confirmed
+                    }
+                    Err(err) => self.resync_offset(&params, err, &mut attempts).await?,
+                };
+                state = confirmed;
+
+                params.progress.confirmed(state.offset);
+
+                if state.offset < chunk_start || state.offset > chunk_end {
+                    return Err(Error::WrongUploadOffsetError);
+                }
+
+                if state.offset == chunk_end {
+                    break;
+                }
+            }
+
+            if upload_length.is_some() {
+                params.progress.completed(state.offset);
+                return Ok(state.into_result(url));
             }
         }
     }
@@ -223,7 +513,7 @@ where
     pub async fn get_server_info(&self, url: &str) -> Result<ServerInfo, Error> {
         let req = self.create_request(HttpMethod::Options, url, None, None);
 
-        let response = self.http_handler.handle_request(req).await?;
+        let response = self.send(req).await?;
 
         if ![200_usize, 204].contains(&response.status_code) {
             return Err(Error::UnexpectedStatusCode(response.status_code));
@@ -232,63 +522,380 @@ where
         let supported_versions: Vec<String> = response
             .headers
             .get_by_key(headers::TUS_VERSION)
-            .unwrap()
+            .ok_or_else(|| Error::MissingHeader(headers::TUS_VERSION.to_owned()))?
             .split(',')
             .map(String::from)
             .collect();
         let extensions: Vec<TusExtension> =
             if let Some(ext) = response.headers.get_by_key(headers::TUS_EXTENSION) {
-                ext.split(',')
-                    .map(str::parse)
-                    .filter(Result::is_ok)
-                    .map(Result::unwrap)
-                    .collect()
+                ext.split(',').filter_map(|e| e.parse().ok()).collect()
             } else {
                 Vec::new()
             };
         let max_upload_size = response
             .headers
             .get_by_key(headers::TUS_MAX_SIZE)
-            .and_then(|h| h.parse::<usize>().ok());
+            .and_then(|h| h.trim().parse::<u64>().ok());
+        let checksum_algorithms: Vec<ChecksumAlgorithm> = response
+            .headers
+            .get_by_key(headers::TUS_CHECKSUM_ALGORITHM)
+            .map(|algs| algs.split(',').filter_map(|a| a.parse().ok()).collect())
+            .unwrap_or_default();
 
         Ok(ServerInfo {
             supported_versions,
             extensions,
             max_upload_size,
+            checksum_algorithms,
         })
     }
 
     /// Create a file on the server, receiving the upload URL of the file.
-    pub async fn create(&self, url: &str, len: usize) -> Result<String, Error> {
-        self.create_with_metadata(url, len, HashMap::new()).await
+    pub async fn create(&self, url: &str, len: u64) -> Result<String, Error> {
+        self.create_with_metadata(url, len, Metadata::new()).await
     }
 
     /// Create a file on the server including the specified metadata, receiving the upload URL of the file.
     pub async fn create_with_metadata(
         &self,
         url: &str,
-        len: usize,
-        metadata: HashMap<String, String>,
+        len: u64,
+        metadata: Metadata,
     ) -> Result<String, Error> {
-        let mut headers = default_headers();
+        let mut headers = create_metadata_headers(&metadata);
         headers.insert(headers::UPLOAD_LENGTH.to_owned(), len.to_string());
-        if !metadata.is_empty() {
-            let data = metadata
-                .iter()
-                .map(|(key, value)| format!("{} {}", key, STANDARD.encode(value)))
-                .collect::<Vec<_>>()
-                .join(",");
-            headers.insert(headers::UPLOAD_METADATA.to_owned(), data);
+
+        self.create_with_headers(url, headers, None)
+            .await
+            .map(|(location, _)| location)
+    }
+
+    /// Create a file on the server and upload it, receiving the upload URL of the file.
+    ///
+    /// If the server supports the creation-with-upload extension, the first chunk of the file is sent along with the request creating the file, which saves a round trip for every file. Files smaller than a single chunk are uploaded completely by that request.
+    /// If the server doesn't answer the request for its information, the file is created and then uploaded. Other errors requesting it, such as failing to connect, are returned.
+    pub async fn create_and_upload<U>(
+        &self,
+        url: &str,
+        source: U,
+        metadata: Metadata,
+    ) -> Result<UploadResult, Error>
+    where
+        U: UploadSource,
+    {
+        self.create_and_upload_with_chunk_size(url, source, metadata, self.config.chunk_size)
+            .await
+    }
+
+    /// Create a file on the server and upload it with the given chunk size, receiving the upload URL of the file.
+    ///
+    /// If the upload expires before it is complete and `ClientBuilder::recreate_expired_uploads` is enabled, the file is created again with the same metadata and the upload is restarted.
+    pub async fn create_and_upload_with_chunk_size<U>(
+        &self,
+        url: &str,
+        source: U,
+        metadata: Metadata,
+        chunk_size: usize,
+    ) -> Result<UploadResult, Error>
+    where
+        U: UploadSource,
+    {
+        let mut recreations = 0;
+        loop {
+            match self
+                .create_and_upload_once(url, &source, &metadata, chunk_size)
+                .await
+            {
+                Err(Error::Gone) if self.should_recreate(&mut recreations) => {}
+                result => return result,
+            }
+        }
+    }
+
+    async fn create_and_upload_once<U>(
+        &self,
+        url: &str,
+        source: U,
+        metadata: &Metadata,
+        chunk_size: usize,
+    ) -> Result<UploadResult, Error>
+    where
+        U: UploadSource,
+    {
+        let file_len = source.len()?;
+
+        // sending the first chunk along is only an optimisation, so without server information the file is created and uploaded separately
+        let server_info = self.optional_server_info(url).await?;
+        let checksum_algorithm = server_info.as_ref().and_then(|server_info| {
+            server_info.preferred_checksum_algorithm(&self.config.checksum_preference)
+        });
+        let chunk_size = chunk_size.max(1);
+        let upload_params = |location, checksum_algorithm| UploadParams {
+            url: location,
+            chunk_size,
+            checksum_algorithm,
+            progress: ProgressTracker::new(
+                self.config.progress_listener.as_deref(),
+                0,
+                Some(file_len),
+            ),
+            control: None,
+        };
+
+        let mut headers = create_metadata_headers(metadata);
+        headers.insert(headers::UPLOAD_LENGTH.to_owned(), file_len.to_string());
+
+        let creation_with_upload = match &server_info {
+            Some(server_info) => server_info
+                .extensions
+                .contains(&TusExtension::CreationWithUpload),
+            None => false,
+        };
+        if !creation_with_upload {
+            let (location, response_headers) = self.create_with_headers(url, headers, None).await?;
+            let checksum_algorithm = match server_info {
+                Some(_) => checksum_algorithm,
+                None => self.negotiate_checksum_algorithm(&location).await?,
+            };
+            let state = UploadPosition {
+                offset: 0,
+                expires: parse_expires(&response_headers)?,
+            };
+            return self
+                .upload_from(
+                    &upload_params(&location, checksum_algorithm),
+                    &source,
+                    file_len,
+                    state,
+                )
+                .await;
+        }
+
+        let chunk_len = file_len.min(chunk_size as u64);
+        let mut chunk = ChunkReader::new(&source, 0, chunk_len);
+        let mut body = HttpBody::Reader {
+            reader: &mut chunk,
+            len: chunk_len,
+        };
+
+        headers.insert(
+            headers::CONTENT_TYPE.to_owned(),
+            "application/offset+octet-stream".to_owned(),
+        );
+        if let Some(algorithm) = checksum_algorithm {
+            headers.insert(
+                headers::UPLOAD_CHECKSUM.to_owned(),
+                checksum_header(algorithm, &mut body).await?,
+            );
+            body.rewind().await?;
+        }
+
+        let (location, response_headers) =
+            self.create_with_headers(url, headers, Some(body)).await?;
+
+        // the server may choose not to accept any of the data sent along with the request
+        let state = UploadPosition {
+            offset: match response_headers.get_by_key(headers::UPLOAD_OFFSET) {
+                Some(offset) => parse_size(headers::UPLOAD_OFFSET, offset)?,
+                None => 0,
+            },
+            expires: parse_expires(&response_headers)?,
+        };
+
+        self.upload_from(
+            &upload_params(&location, checksum_algorithm),
+            &source,
+            file_len,
+            state,
+        )
+        .await
+    }
+
+    /// Create a file on the server without specifying its length, receiving the upload URL of the file.
+    ///
+    /// The length is declared while uploading, by `upload_stream`. This requires the server to support the creation-defer-length extension.
+    pub async fn create_with_deferred_length(
+        &self,
+        url: &str,
+        metadata: Metadata,
+    ) -> Result<String, Error> {
+        let mut headers = create_metadata_headers(&metadata);
+        headers.insert(headers::UPLOAD_DEFER_LENGTH.to_owned(), "1".to_owned());
+
+        self.create_with_headers(url, headers, None)
+            .await
+            .map(|(location, _)| location)
+    }
+
+    /// Create a partial upload on the server, receiving the upload URL of the partial upload.
+    ///
+    /// Partial uploads are uploaded like any other file, and are combined into a single file using `concatenate`. This requires the server to support the concatenation extension.
+    pub async fn create_partial(&self, url: &str, len: u64) -> Result<String, Error> {
+        let mut headers = Headers::new();
+        headers.insert(headers::UPLOAD_LENGTH.to_owned(), len.to_string());
+        headers.insert(headers::UPLOAD_CONCAT.to_owned(), "partial".to_owned());
+
+        self.create_with_headers(url, headers, None)
+            .await
+            .map(|(location, _)| location)
+    }
+
+    /// Combine the partial uploads at `partial_urls` into a single file on the server, receiving the upload URL of the file.
+    pub async fn concatenate(
+        &self,
+        url: &str,
+        partial_urls: &[String],
+        metadata: Metadata,
+    ) -> Result<String, Error> {
+        self.concatenate_with_headers(url, partial_urls, &metadata)
+            .await
+            .map(|(location, _)| location)
+    }
+
+    async fn concatenate_with_headers(
+        &self,
+        url: &str,
+        partial_urls: &[String],
+        metadata: &Metadata,
+    ) -> Result<(String, Headers), Error> {
+        let mut headers = create_metadata_headers(metadata);
+        headers.insert(
+            headers::UPLOAD_CONCAT.to_owned(),
+            format!("final;{}", partial_urls.join(" ")),
+        );
+
+        self.create_with_headers(url, headers, None).await
+    }
+
+    /// Upload a file in parallel, using the concatenation extension, receiving the upload URL of the file.
+    ///
+    /// The file is split into `parts` byte ranges of roughly equal size. Each range of `source` is uploaded as a partial upload, and the partial uploads are concatenated once all of them are complete.
+    /// A partial upload which is interrupted by an error the retry policy retries is resumed from where it left off, after the delay of the retry policy.
+    /// The progress listener of the client receives the progress of the whole file, summed over all parts.
+    ///
+    /// Fails with `Error::ConcatenationUnsupported` before anything is uploaded if the server doesn't support the concatenation extension.
+    pub async fn upload_parallel<U>(
+        &self,
+        url: &str,
+        source: U,
+        parts: usize,
+        metadata: Metadata,
+    ) -> Result<UploadResult, Error>
+    where
+        U: UploadSource,
+    {
+        let file_len = source.len()?;
+
+        if !self
+            .cached_server_info(url)
+            .await?
+            .extensions
+            .contains(&TusExtension::Concatenation)
+        {
+            return Err(Error::ConcatenationUnsupported);
         }
 
-        let req = self.create_request(HttpMethod::Post, url, None, Some(headers));
+        let parts = (parts as u64).clamp(1, file_len.max(1));
+        let part_len = file_len / parts;
+        let ranges = (0..parts).map(|part| {
+            let start = part * part_len;
+            let len = if part == parts - 1 {
+                file_len - start
+            } else {
+                part_len
+            };
+            (start, len)
+        });
+
+        let progress = self
+            .config
+            .progress_listener
+            .as_deref()
+            .map(|listener| PartsProgress::new(listener, parts as usize, file_len));
+        let source = &source;
+        let progress = &progress;
+        let partial_urls =
+            try_join_all(ranges.enumerate().map(|(index, (start, len))| async move {
+                let partial_url = self.create_partial(url, len).await?;
+                let listener = progress
+                    .as_ref()
+                    .map(|progress| progress.part(index, start));
+                self.upload_partial(
+                    &partial_url,
+                    SourceRange::new(source, start, len),
+                    listener
+                        .as_ref()
+                        .map(|listener| listener as &dyn ProgressListener),
+                )
+                .await?;
+                Ok::<_, Error>(partial_url)
+            }))
+            .await?;
+
+        let (location, response_headers) = self
+            .concatenate_with_headers(url, &partial_urls, &metadata)
+            .await?;
+        if let Some(progress) = progress {
+            progress.completed();
+        }
 
-        let response = self.http_handler.handle_request(req).await?;
+        Ok(UploadResult {
+            url: location,
+            bytes_uploaded: file_len,
+            expires: parse_expires(&response_headers)?,
+        })
+    }
+
+    /// Upload a partial upload, resuming it if it is interrupted and the retry policy allows it.
+    async fn upload_partial<U>(
+        &self,
+        url: &str,
+        source: U,
+        listener: Option<&dyn ProgressListener>,
+    ) -> Result<(), Error>
+    where
+        U: UploadSource,
+    {
+        let mut failed_attempts = 0;
+        loop {
+            match self
+                .upload_with_listener(url, &source, self.config.chunk_size, listener, None)
+                .await
+            {
+                Err(err)
+                    if self
+                        .config
+                        .retry_policy
+                        .should_retry(&err, failed_attempts + 1) =>
+                {
+                    failed_attempts += 1;
+                    self.sleep(self.config.retry_policy.delay(failed_attempts))
+                        .await;
+                }
+                result => return result.map(|_| ()),
+            }
+        }
+    }
+
+    /// Send a creation request, receiving the upload URL of the file and the headers of the response.
+    async fn create_with_headers(
+        &self,
+        url: &str,
+        headers: Headers,
+        body: Option<HttpBody<'_>>,
+    ) -> Result<(String, Headers), Error> {
+        let req = self.create_request(HttpMethod::Post, url, body, Some(headers));
+
+        let response = self.send(req).await?;
 
         if response.status_code == 413 {
             return Err(Error::FileTooLarge);
         }
 
+        if response.status_code == 401 {
+            return Err(Error::Unauthorized);
+        }
+
         if response.status_code != 201 {
             return Err(Error::UnexpectedStatusCode(response.status_code));
         }
@@ -299,33 +906,306 @@ where
             return Err(Error::MissingHeader(headers::LOCATION.to_owned()));
         }
 
-        Ok(location.unwrap().to_owned())
+        Ok((location.unwrap().to_owned(), response.headers))
+    }
+
+    /// Find the upload stored for `fingerprint`, or create a new one if there is none, receiving the upload URL of the file.
+    ///
+    /// A stored upload is only used if the server still knows it and its length matches the fingerprint. Otherwise, a new upload is created with `metadata` and stored for `fingerprint`.
+    pub async fn create_or_resume<S>(
+        &self,
+        url: &str,
+        store: &S,
+        fingerprint: &Fingerprint,
+        metadata: Metadata,
+    ) -> Result<String, Error>
+    where
+        S: UploadStore + ?Sized,
+    {
+        if let Some(stored) = store.get(fingerprint)? {
+            match self.get_info(&stored.url).await {
+                Ok(info) if info.total_size.is_none_or(|size| size == fingerprint.size) => {
+                    return Ok(stored.url);
+                }
+                Ok(_) | Err(Error::NotFoundError) | Err(Error::Gone) => {
+                    store.remove(fingerprint)?
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        let upload_url = self
+            .create_with_metadata(url, fingerprint.size, metadata.clone())
+            .await?;
+        store.set(
+            fingerprint,
+            StoredUpload {
+                url: upload_url.clone(),
+                metadata,
+            },
+        )?;
+
+        Ok(upload_url)
+    }
+
+    /// Upload a file, resuming the upload stored for `fingerprint` if there is one, receiving the upload URL of the file.
+    ///
+    /// The upload is removed from `store` once it is complete. If the upload is interrupted, calling this method again, even from another process, resumes it.
+    /// If the upload expires before it is complete and `ClientBuilder::recreate_expired_uploads` is enabled, the file is created again with the same metadata and the upload is restarted.
+    pub async fn upload_with_store<U, S>(
+        &self,
+        url: &str,
+        source: U,
+        store: &S,
+        fingerprint: &Fingerprint,
+        metadata: Metadata,
+    ) -> Result<UploadResult, Error>
+    where
+        U: UploadSource,
+        S: UploadStore + ?Sized,
+    {
+        let mut recreations = 0;
+        loop {
+            let upload_url = self
+                .create_or_resume(url, store, fingerprint, metadata.clone())
+                .await?;
+
+            match self.upload(&upload_url, &source).await {
+                Err(Error::Gone) if self.should_recreate(&mut recreations) => {
+                    store.remove(fingerprint)?
+                }
+                Err(err) => return Err(err),
+                Ok(result) => {
+                    store.remove(fingerprint)?;
+                    return Ok(result);
+                }
+            }
+        }
     }
 
     /// Delete a file on the server.
     pub async fn delete(&self, url: &str) -> Result<(), Error> {
-        let req = self.create_request(HttpMethod::Delete, url, None, Some(default_headers()));
+        let req = self.create_request(HttpMethod::Delete, url, None, None);
 
-        let response = self.http_handler.handle_request(req).await?;
+        let response = self.send(req).await?;
 
         if response.status_code != 204 {
-            return Err(Error::UnexpectedStatusCode(response.status_code));
+            return Err(status_error(response.status_code));
         }
 
         Ok(())
     }
 
+    /// Send a single chunk to the server, receiving the new upload offset and expiration time.
+    ///
+    /// The chunk is sent again if the server reports a checksum mismatch.
+    async fn upload_chunk(
+        &self,
+        url: &str,
+        offset: u64,
+        mut chunk: HttpBody<'_>,
+        checksum_algorithm: Option<ChecksumAlgorithm>,
+        upload_length: Option<u64>,
+    ) -> Result<UploadPosition, Error> {
+        let mut checksum_mismatches = 0;
+
+        loop {
+            let mut headers = create_upload_headers(offset);
+            if let Some(algorithm) = checksum_algorithm {
+                headers.insert(
+                    headers::UPLOAD_CHECKSUM.to_owned(),
+                    checksum_header(algorithm, &mut chunk).await?,
+                );
+            }
+            if let Some(upload_length) = upload_length {
+                headers.insert(headers::UPLOAD_LENGTH.to_owned(), upload_length.to_string());
+            }
+
+            // a streamed chunk is read from its start again when it is resent
+            chunk.rewind().await?;
+            let req = self.create_request(
+                HttpMethod::Patch,
+                url,
+                Some(chunk.reborrow()),
+                Some(headers),
+            );
+
+            let response = self.send(req).await?;
+
+            if response.status_code == 460 {
+                checksum_mismatches += 1;
+                if checksum_mismatches >= MAX_CHECKSUM_ATTEMPTS {
+                    return Err(Error::ChecksumMismatch);
+                }
+                continue;
+            }
+
+            if response.status_code == 409 {
+                return Err(Error::WrongUploadOffsetError);
+            }
+
+            if response.status_code != 204 {
+                return Err(status_error(response.status_code));
+            }
+
+            let upload_offset = match response.headers.get_by_key(headers::UPLOAD_OFFSET) {
+                Some(offset) => Ok(offset),
+                None => Err(Error::MissingHeader(headers::UPLOAD_OFFSET.to_owned())),
+            }?;
+
+            return Ok(UploadPosition {
+                offset: parse_size(headers::UPLOAD_OFFSET, upload_offset)?,
+                expires: parse_expires(&response.headers)?,
+            });
+        }
+    }
+
+    /// Decides whether to retry after uploading a chunk failed with `err`, receiving the state to continue the upload from.
+    ///
+    /// If the server rejected the offset of the chunk, the upload continues from the offset the server reports, without waiting. This happens up to `max_offset_resyncs` times in a row.
+    /// Otherwise, the `Client` waits according to the retry policy before each retry and asks the server for the current upload offset. Requesting the offset is retried as well.
+    async fn resync_offset(
+        &self,
+        params: &UploadParams<'_>,
+        mut err: Error,
+        attempts: &mut Attempts,
+    ) -> Result<UploadPosition, Error> {
+        if let Error::WrongUploadOffsetError = err {
+            attempts.offset_resyncs += 1;
+            if attempts.offset_resyncs <= self.config.max_offset_resyncs {
+                match self.get_info(params.url).await {
+                    Ok(info) => return Ok(UploadPosition::from(info)),
+                    Err(info_err) => err = info_err,
+                }
+            }
+        }
+
+        loop {
+            attempts.failed += 1;
+            if !self.config.retry_policy.should_retry(&err, attempts.failed) {
+                return Err(err);
+            }
+
+            let delay = self.config.retry_policy.delay(attempts.failed);
+            params.progress.retrying(attempts.failed, delay, &err);
+            self.sleep(delay).await;
+
+            match self.get_info(params.url).await {
+                Ok(info) => return Ok(UploadPosition::from(info)),
+                Err(info_err) => err = info_err,
+            }
+        }
+    }
+
+    /// Whether an upload which expired should be created again, counting the number of times it was.
+    fn should_recreate(&self, recreations: &mut usize) -> bool {
+        *recreations += 1;
+        self.config.recreate_expired_uploads && *recreations <= MAX_EXPIRED_RECREATIONS
+    }
+
+    /// Waits for `delay` on the timer of the client before retrying a request.
+    async fn sleep(&self, delay: Duration) {
+        if !delay.is_zero() {
+            self.config.timer.sleep(delay).await;
+        }
+    }
+
+    /// Picks the checksum algorithm to use for uploads to `url`, if the server supports the checksum extension.
+    ///
+    /// The server information of an endpoint the upload URL is below is reused if it was cached, such as the endpoint the upload was created at by `create_and_upload`. Otherwise it is requested from the parent of the upload URL, which is where tus servers usually create uploads, and cached, so it is only requested once per server.
+    async fn negotiate_checksum_algorithm(
+        &self,
+        url: &str,
+    ) -> Result<Option<ChecksumAlgorithm>, Error> {
+        if self.config.checksum_preference.is_empty() {
+            return Ok(None);
+        }
+
+        let endpoint = self
+            .cached_endpoint(url)
+            .unwrap_or_else(|| creation_endpoint(url).to_owned());
+        let server_info = match self.optional_server_info(&endpoint).await? {
+            Some(server_info) => server_info,
+            None => {
+                // the server isn't required to answer `OPTIONS` on every URL, so the upload is still sent
+                log::warn!(
+                    "The server didn't return its information at {}, uploading {} without checksums",
+                    endpoint,
+                    url
+                );
+                return Ok(None);
+            }
+        };
+
+        Ok(server_info.preferred_checksum_algorithm(&self.config.checksum_preference))
+    }
+
+    /// The longest endpoint with cached server information which `upload_url` is below.
+    fn cached_endpoint(&self, upload_url: &str) -> Option<String> {
+        let cache = self.server_info_cache.lock().ok()?;
+        cache
+            .keys()
+            .filter(|endpoint| {
+                upload_url
+                    .strip_prefix(endpoint.as_str())
+                    .is_some_and(|rest| {
+                        !rest.is_empty() && (endpoint.ends_with('/') || rest.starts_with('/'))
+                    })
+            })
+            .max_by_key(|endpoint| endpoint.len())
+            .cloned()
+    }
+
+    /// Get information about the tus server like `cached_server_info`, or `None` if the server doesn't answer the request for it.
+    ///
+    /// Errors other than an unexpected response, such as failing to connect, are returned.
+    async fn optional_server_info(&self, url: &str) -> Result<Option<ServerInfo>, Error> {
+        match self.cached_server_info(url).await {
+            Ok(server_info) => Ok(Some(server_info)),
+            Err(Error::UnexpectedStatusCode(_)) | Err(Error::MissingHeader(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Get information about the tus server, reusing the information from an earlier request to `url` if there was one.
+    async fn cached_server_info(&self, url: &str) -> Result<ServerInfo, Error> {
+        if let Some(server_info) = self
+            .server_info_cache
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(url).cloned())
+        {
+            return Ok(server_info);
+        }
+
+        let server_info = self.get_server_info(url).await?;
+        if let Ok(mut cache) = self.server_info_cache.lock() {
+            cache.insert(url.to_owned(), server_info.clone());
+        }
+
+        Ok(server_info)
+    }
+
     fn create_request<'b>(
         &self,
         method: HttpMethod,
         url: &str,
-        body: Option<&'b [u8]>,
+        body: Option<HttpBody<'b>>,
         headers: Option<Headers>,
     ) -> HttpRequest<'b> {
-        let mut headers = headers.unwrap_or_default();
+        let mut request_headers = self.config.headers.clone();
+        request_headers.extend(headers.unwrap_or_default());
 
-        let method = if self.use_method_override {
-            headers.insert(
+        // the `Tus-Resumable` header is required in every request, except for `OPTIONS` requests
+        if method != HttpMethod::Options {
+            request_headers.insert(
+                headers::TUS_RESUMABLE.to_owned(),
+                self.config.tus_version.clone(),
+            );
+        }
+
+        let method = if self.config.method_override.applies_to(method) {
+            request_headers.insert(
                 headers::X_HTTP_METHOD_OVERRIDE.to_owned(),
                 method.to_string(),
             );
@@ -338,7 +1218,94 @@ where
             method,
             url: String::from(url),
             body,
-            headers,
+            headers: request_headers,
+        }
+    }
+
+    /// Send a request, authenticated with the value of the `TokenProvider` if there is one.
+    ///
+    /// If the server rejects the credentials, the `TokenProvider` is asked to refresh them and the request is sent once more.
+    async fn send(&self, mut req: HttpRequest<'_>) -> Result<HttpResponse, Error> {
+        let Some(token_provider) = &self.config.token_provider else {
+            return self.send_once(req).await;
+        };
+
+        set_authorization(&mut req.headers, token_provider.authorization().await?);
+        let response = self.send_once(req.reborrow()).await?;
+        if response.status_code != 401 {
+            return Ok(response);
+        }
+
+        set_authorization(&mut req.headers, token_provider.refresh().await?);
+        if let Some(body) = &mut req.body {
+            body.rewind().await?;
+        }
+        self.send_once(req).await
+    }
+
+    /// Send a request through the HTTP handler, failing with `Error::Timeout` if it takes longer than the request timeout.
+    async fn send_once(&self, req: HttpRequest<'_>) -> Result<HttpResponse, Error> {
+        let response = self.http_handler.handle_request(req);
+        let Some(timeout) = self.config.request_timeout else {
+            return response.await;
+        };
+
+        pin_mut!(response);
+        match select(response, self.config.timer.sleep(timeout)).await {
+            Either::Left((response, _)) => response,
+            Either::Right(_) => Err(Error::Timeout),
+        }
+    }
+}
+
+/// The settings shared by all chunks of a single upload.
+struct UploadParams<'a> {
+    url: &'a str,
+    chunk_size: usize,
+    checksum_algorithm: Option<ChecksumAlgorithm>,
+    progress: ProgressTracker<'a>,
+    control: Option<&'a UploadControl>,
+}
+
+/// The buffers of an upload which reads the next chunk while the current chunk is sent.
+#[derive(Default)]
+struct ReadAhead {
+    current: Vec<u8>,
+    next: Vec<u8>,
+    /// The offset of the chunk in `next`, if it was read completely.
+    next_offset: Option<u64>,
+}
+
+/// Counts the failures since the last chunk which was uploaded successfully.
+#[derive(Default)]
+struct Attempts {
+    /// Requests which failed, and were retried according to the retry policy.
+    failed: usize,
+    /// Chunks which were rejected because of their offset, after which the offset was requested from the server.
+    offset_resyncs: usize,
+}
+
+/// The position of an upload, as last reported by the server.
+struct UploadPosition {
+    offset: u64,
+    expires: Option<SystemTime>,
+}
+
+impl UploadPosition {
+    fn into_result(self, url: &str) -> UploadResult {
+        UploadResult {
+            url: url.to_owned(),
+            bytes_uploaded: self.offset,
+            expires: self.expires,
+        }
+    }
+}
+
+impl From<UploadInfo> for UploadPosition {
+    fn from(info: UploadInfo) -> Self {
+        UploadPosition {
+            offset: info.bytes_uploaded,
+            expires: info.expires,
         }
     }
 }
@@ -347,29 +1314,65 @@ where
 #[derive(Debug)]
 pub struct UploadInfo {
     /// How many bytes have been uploaded.
-    pub bytes_uploaded: usize,
+    pub bytes_uploaded: u64,
     /// The total size of the file.
-    pub total_size: Option<usize>,
+    pub total_size: Option<u64>,
     /// Metadata supplied when the file was created.
-    pub metadata: Option<HashMap<String, String>>,
+    pub metadata: Option<Metadata>,
+    /// The time after which the server may remove an unfinished upload, if the server supports the expiration extension.
+    pub expires: Option<SystemTime>,
+}
+
+/// Describes a file after uploading it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadResult {
+    /// The upload URL of the file. This differs from the URL passed in if the upload expired and was created again.
+    pub url: String,
+    /// How many bytes have been uploaded.
+    pub bytes_uploaded: u64,
+    /// The time after which the server may remove the upload, as last reported by the server. Servers usually don't let complete uploads expire.
+    pub expires: Option<SystemTime>,
 }
 
 /// Describes the tus enabled server.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ServerInfo {
     /// The different versions of the tus protocol supported by the server, ordered by preference.
     pub supported_versions: Vec<String>,
     /// The extensions to the protocol supported by the server.
     pub extensions: Vec<TusExtension>,
     /// The maximum supported total size of a file.
-    pub max_upload_size: Option<usize>,
+    pub max_upload_size: Option<u64>,
+    /// The checksum algorithms supported by the server.
+    pub checksum_algorithms: Vec<ChecksumAlgorithm>,
+}
+
+impl ServerInfo {
+    /// The first algorithm of `preference` the server supports, if the server supports the checksum extension.
+    fn preferred_checksum_algorithm(
+        &self,
+        preference: &[ChecksumAlgorithm],
+    ) -> Option<ChecksumAlgorithm> {
+        if !self.extensions.contains(&TusExtension::Checksum) {
+            return None;
+        }
+
+        preference
+            .iter()
+            .find(|algorithm| self.checksum_algorithms.contains(algorithm))
+            .copied()
+    }
 }
 
 /// Enumerates the extensions to the tus protocol.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TusExtension {
     /// The server supports creating files.
     Creation,
+    /// The server supports creating files without knowing their length up front.
+    CreationDeferLength,
+    /// The server supports including the first chunk of a file in the request creating it.
+    CreationWithUpload,
     //// The server supports setting expiration time on files and uploads.
     Expiration,
     /// The server supports verifying checksums of uploaded chunks.
@@ -386,6 +1389,8 @@ impl FromStr for TusExtension {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.trim().to_lowercase().as_str() {
             "creation" => Ok(TusExtension::Creation),
+            "creation-defer-length" => Ok(TusExtension::CreationDeferLength),
+            "creation-with-upload" => Ok(TusExtension::CreationWithUpload),
             "expiration" => Ok(TusExtension::Expiration),
             "checksum" => Ok(TusExtension::Checksum),
             "termination" => Ok(TusExtension::Termination),
@@ -404,6 +1409,10 @@ pub enum Error {
     NotFoundError,
     /// A required header was missing from the server response.
     MissingHeader(String),
+    /// A header in the server response has a value which is not valid for that header.
+    InvalidHeader(String),
+    /// A metadata key is empty, or contains characters other than visible ASCII characters, or a comma.
+    InvalidMetadataKey(String),
     /// An error occurred while doing disk IO. This may be while reading a file, or during a network call.
     IoError(io::Error),
     /// Unable to parse a value, which should be an integer.
@@ -416,8 +1425,22 @@ pub enum Error {
     WrongUploadOffsetError,
     /// The specified file is larger that what is supported by the server.
     FileTooLarge,
+    /// The upload expired or was removed, and is no longer available on the server.
+    Gone,
+    /// The upload is locked by another request to it, which is still in progress.
+    Locked,
+    /// The upload was cancelled through its `UploadHandle`.
+    Cancelled,
     /// An error occurred in the HTTP handler.
     HttpHandlerError(String),
+    /// A request took longer than the request timeout of the `Client`.
+    Timeout,
+    /// The server rejected the credentials of the request, even after they were refreshed.
+    Unauthorized,
+    /// The server repeatedly rejected a chunk because its checksum did not match.
+    ChecksumMismatch,
+    /// The server doesn't support the concatenation extension, which is needed to upload a file in parallel.
+    ConcatenationUnsupported,
 }
 
 impl Display for Error {
@@ -426,13 +1449,22 @@ impl Display for Error {
             Error::UnexpectedStatusCode(status_code) => format!("The status code returned by the server was not one of the expected ones: {}", status_code),
             Error::NotFoundError => "The file specified was not found by the server".to_string(),
             Error::MissingHeader(header_name) => format!("The '{}' header was missing from the server response", header_name),
+            Error::InvalidHeader(header_name) => format!("The '{}' header in the server response has an invalid value", header_name),
+            Error::InvalidMetadataKey(key) => format!("The metadata key '{}' is empty, or contains characters other than visible ASCII characters, or a comma", key),
             Error::IoError(error) => format!("An error occurred while doing disk IO. This may be while reading a file, or during a network call: {}", error),
             Error::ParsingError(error) => format!("Unable to parse a value, which should be an integer: {}", error),
             Error::UnequalSizeError => "The size of the specified file, and the file size reported by the server do not match".to_string(),
             Error::FileReadError => "Unable to read the specified file".to_string(),
             Error::WrongUploadOffsetError => "The client tried to upload the file with an incorrect offset".to_string(),
             Error::FileTooLarge => "The specified file is larger that what is supported by the server".to_string(),
+            Error::Gone => "The upload expired or was removed, and is no longer available on the server".to_string(),
+            Error::Locked => "The upload is locked by another request to it, which is still in progress".to_string(),
+            Error::Cancelled => "The upload was cancelled".to_string(),
             Error::HttpHandlerError(message) => format!("An error occurred in the HTTP handler: {}", message),
+            Error::Timeout => "The request timed out".to_string(),
+            Error::Unauthorized => "The server rejected the credentials of the request".to_string(),
+            Error::ChecksumMismatch => "The server repeatedly rejected a chunk because its checksum did not match".to_string(),
+            Error::ConcatenationUnsupported => "The server doesn't support the concatenation extension, which is needed to upload a file in parallel".to_string(),
         };
 
         write!(f, "{}", message)?;
@@ -467,8 +1499,104 @@ impl HeaderMap for HashMap<String, String> {
     }
 }
 
-fn create_upload_headers(progress: usize) -> Headers {
-    let mut headers = default_headers();
+/// The size of the pieces a streamed chunk is read in to calculate its checksum.
+const CHECKSUM_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Create the `Upload-Checksum` header for `chunk`, reading a streamed chunk piece by piece.
+async fn checksum_header(
+    algorithm: ChecksumAlgorithm,
+    chunk: &mut HttpBody<'_>,
+) -> Result<String, Error> {
+    let reader = match chunk {
+        HttpBody::Bytes(bytes) => return Ok(algorithm.header_value(bytes)),
+        HttpBody::Reader { reader, .. } => reader,
+    };
+
+    reader.seek(SeekFrom::Start(0)).await?;
+    let mut hasher = algorithm.hasher();
+    let mut buffer = vec![0; CHECKSUM_BUFFER_SIZE];
+    loop {
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher.header_value())
+}
+
+/// Fill `buffer` until the end of the reader is reached or the buffer is filled, returning the number of bytes read.
+async fn read_chunk<R>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut bytes_read = 0;
+    while bytes_read < buffer.len() {
+        let read = reader.read(&mut buffer[bytes_read..]).await?;
+        if read == 0 {
+            break;
+        }
+        bytes_read += read;
+    }
+    Ok(bytes_read)
+}
+
+/// The URL of the endpoint an upload was created at, which is the parent of the upload URL, unless the upload URL has no parent.
+fn creation_endpoint(upload_url: &str) -> &str {
+    match upload_url.trim_end_matches('/').rsplit_once('/') {
+        Some((endpoint, _)) if !endpoint.is_empty() && !endpoint.ends_with('/') => endpoint,
+        _ => upload_url,
+    }
+}
+
+/// Replace the `Authorization` header, however its name is capitalized.
+fn set_authorization(headers: &mut Headers, value: String) {
+    headers.retain(|name, _| !name.eq_ignore_ascii_case(headers::AUTHORIZATION));
+    headers.insert(headers::AUTHORIZATION.to_owned(), value);
+}
+
+/// The error for a request to an upload which failed with `status_code`.
+fn status_error(status_code: usize) -> Error {
+    match status_code {
+        401 => Error::Unauthorized,
+        404 => Error::NotFoundError,
+        410 => Error::Gone,
+        423 => Error::Locked,
+        _ => Error::UnexpectedStatusCode(status_code),
+    }
+}
+
+/// Parse the value of the `Upload-Offset` or `Upload-Length` header `name`, which needs to be a non-negative integer fitting in a `u64`.
+fn parse_size(name: &str, value: &str) -> Result<u64, Error> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Error::InvalidHeader(name.to_owned()));
+    }
+
+    Ok(value.parse()?)
+}
+
+/// Parse the `Upload-Expires` header, if the server sent one.
+fn parse_expires(headers: &Headers) -> Result<Option<SystemTime>, Error> {
+    headers
+        .get_by_key(headers::UPLOAD_EXPIRES)
+        .map(|expires| {
+            httpdate::parse_http_date(expires)
+                .map_err(|_| Error::InvalidHeader(headers::UPLOAD_EXPIRES.to_owned()))
+        })
+        .transpose()
+}
+
+fn create_metadata_headers(metadata: &Metadata) -> Headers {
+    let mut headers = Headers::new();
+    if !metadata.is_empty() {
+        headers.insert(headers::UPLOAD_METADATA.to_owned(), metadata.to_string());
+    }
+    headers
+}
+
+fn create_upload_headers(progress: u64) -> Headers {
+    let mut headers = Headers::new();
     headers.insert(
         headers::CONTENT_TYPE.to_owned(),
         "application/offset+octet-stream".to_owned(),
@@ -0,0 +1,182 @@
+use crate::{headers, Error};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::de::Error as DeError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::btree_map::{BTreeMap, Iter};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::str::{self, FromStr};
+
+/// The metadata of an upload, sent to the server in the `Upload-Metadata` header.
+///
+/// Values are arbitrary bytes. A key without a value is stored with an empty value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    entries: BTreeMap<String, Vec<u8>>,
+}
+
+impl Metadata {
+    /// Creates empty metadata.
+    pub fn new() -> Self {
+        Metadata::default()
+    }
+
+    /// Sets the value of `key`, returning the previous value if there was one.
+    ///
+    /// Fails with `Error::InvalidMetadataKey` if `key` is empty, or contains anything but visible ASCII characters other than a comma.
+    pub fn insert<K, V>(&mut self, key: K, value: V) -> Result<Option<Vec<u8>>, Error>
+    where
+        K: Into<String>,
+        V: Into<Vec<u8>>,
+    {
+        let key = key.into();
+        if !is_valid_key(&key) {
+            return Err(Error::InvalidMetadataKey(key));
+        }
+
+        Ok(self.entries.insert(key, value.into()))
+    }
+
+    /// Get the value of `key`.
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.entries.get(key).map(Vec::as_slice)
+    }
+
+    /// Get the value of `key`, if it is valid UTF-8.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(|value| str::from_utf8(value).ok())
+    }
+
+    /// Removes `key`, returning its value if it was present.
+    pub fn remove(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.entries.remove(key)
+    }
+
+    /// Whether a value is set for `key`.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// The number of keys.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no keys are set.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Parses the `Upload-Metadata` header of a server response, skipping entries which can't be decoded.
+    ///
+    /// Keys aren't validated like in `insert`, since other clients may have created the upload with keys which this client wouldn't send, such as keys with non-ASCII characters.
+    pub(crate) fn from_header(s: &str) -> Self {
+        let mut metadata = Metadata::new();
+
+        for pair in s.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (key, value) = match pair.split_once(' ') {
+                Some((key, value)) => match STANDARD.decode(value.trim()) {
+                    Ok(value) => (key, value),
+                    Err(_) => continue,
+                },
+                None => (pair, Vec::new()),
+            };
+            metadata.entries.insert(key.to_owned(), value);
+        }
+
+        metadata
+    }
+
+    /// Iterates over the keys and values, ordered by key.
+    pub fn iter(&self) -> Iter<'_, String, Vec<u8>> {
+        self.entries.iter()
+    }
+}
+
+/// Formats the metadata as the value of the `Upload-Metadata` header: comma separated pairs of a key and its base64 encoded value.
+impl Display for Metadata {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        for (index, (key, value)) in self.entries.iter().enumerate() {
+            if index > 0 {
+                write!(f, ",")?;
+            }
+            if value.is_empty() {
+                write!(f, "{}", key)?;
+            } else {
+                write!(f, "{} {}", key, STANDARD.encode(value))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the value of the `Upload-Metadata` header.
+impl FromStr for Metadata {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidHeader(headers::UPLOAD_METADATA.to_owned());
+        let mut metadata = Metadata::new();
+
+        for pair in s.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (key, value) = match pair.split_once(' ') {
+                Some((key, value)) => (key, STANDARD.decode(value.trim()).map_err(|_| invalid())?),
+                None => (pair, Vec::new()),
+            };
+            metadata.insert(key, value).map_err(|_| invalid())?;
+        }
+
+        Ok(metadata)
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for Metadata {
+    type Error = Error;
+
+    fn try_from(map: HashMap<String, String>) -> Result<Self, Self::Error> {
+        let mut metadata = Metadata::new();
+        for (key, value) in map {
+            metadata.insert(key, value)?;
+        }
+
+        Ok(metadata)
+    }
+}
+
+impl<'a> IntoIterator for &'a Metadata {
+    type Item = (&'a String, &'a Vec<u8>);
+    type IntoIter = Iter<'a, String, Vec<u8>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Serialized as the value of the `Upload-Metadata` header, so binary values stay compact.
+impl Serialize for Metadata {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Metadata {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Keys can't be empty, and can't contain spaces or commas, since those separate the entries of the header.
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.bytes().all(|b| b.is_ascii_graphic() && b != b',')
+}
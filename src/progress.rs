@@ -0,0 +1,222 @@
+use crate::Error;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::Stream;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Describes an event during an upload.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// A chunk of `len` bytes, starting at `offset`, is being sent to the server.
+    ChunkStarted { offset: u64, len: usize },
+    /// The server confirmed it received the file up to `bytes_uploaded`.
+    Progress {
+        bytes_uploaded: u64,
+        total_size: Option<u64>,
+        /// The average upload speed since the upload started, once it can be estimated.
+        bytes_per_second: Option<f64>,
+    },
+    /// A request failed with `error`, and is retried after `delay`.
+    Retrying {
+        attempt: usize,
+        delay: Duration,
+        error: String,
+    },
+    /// The file was uploaded completely.
+    Completed { bytes_uploaded: u64 },
+}
+
+/// Receives the `ProgressEvent`s of an upload.
+pub trait ProgressListener: Send + Sync {
+    fn on_progress(&self, event: &ProgressEvent);
+}
+
+impl<F> ProgressListener for F
+where
+    F: Fn(&ProgressEvent) + Send + Sync,
+{
+    fn on_progress(&self, event: &ProgressEvent) {
+        self(event)
+    }
+}
+
+/// Creates a `ProgressListener` which forwards every event to a `Stream`.
+///
+/// The stream ends once the `ProgressSender` is dropped.
+pub fn progress_channel() -> (ProgressSender, ProgressStream) {
+    let (sender, receiver) = unbounded();
+    (ProgressSender { sender }, ProgressStream { receiver })
+}
+
+/// The sending half of `progress_channel`.
+#[derive(Debug, Clone)]
+pub struct ProgressSender {
+    sender: UnboundedSender<ProgressEvent>,
+}
+
+impl ProgressListener for ProgressSender {
+    fn on_progress(&self, event: &ProgressEvent) {
+        // nobody may be listening anymore, which shouldn't affect the upload
+        let _ = self.sender.unbounded_send(event.clone());
+    }
+}
+
+/// The receiving half of `progress_channel`.
+#[derive(Debug)]
+pub struct ProgressStream {
+    receiver: UnboundedReceiver<ProgressEvent>,
+}
+
+impl Stream for ProgressStream {
+    type Item = ProgressEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+/// Turns the progress of an upload into `ProgressEvent`s.
+pub(crate) struct ProgressTracker<'a> {
+    listener: Option<&'a dyn ProgressListener>,
+    started: Instant,
+    initial_offset: u64,
+    total_size: Option<u64>,
+}
+
+impl<'a> ProgressTracker<'a> {
+    pub(crate) fn new(
+        listener: Option<&'a dyn ProgressListener>,
+        initial_offset: u64,
+        total_size: Option<u64>,
+    ) -> Self {
+        ProgressTracker {
+            listener,
+            started: Instant::now(),
+            initial_offset,
+            total_size,
+        }
+    }
+
+    pub(crate) fn chunk_started(&self, offset: u64, len: usize) {
+        self.emit(|| ProgressEvent::ChunkStarted { offset, len });
+    }
+
+    pub(crate) fn confirmed(&self, bytes_uploaded: u64) {
+        self.emit(|| {
+            let elapsed = self.started.elapsed().as_secs_f64();
+            let bytes_per_second = if elapsed > 0.0 && bytes_uploaded > self.initial_offset {
+                Some((bytes_uploaded - self.initial_offset) as f64 / elapsed)
+            } else {
+                None
+            };
+
+            ProgressEvent::Progress {
+                bytes_uploaded,
+                total_size: self.total_size,
+                bytes_per_second,
+            }
+        });
+    }
+
+    pub(crate) fn retrying(&self, attempt: usize, delay: Duration, error: &Error) {
+        self.emit(|| ProgressEvent::Retrying {
+            attempt,
+            delay,
+            error: error.to_string(),
+        });
+    }
+
+    pub(crate) fn completed(&self, bytes_uploaded: u64) {
+        self.emit(|| ProgressEvent::Completed { bytes_uploaded });
+    }
+
+    fn emit<F>(&self, event: F)
+    where
+        F: FnOnce() -> ProgressEvent,
+    {
+        if let Some(listener) = self.listener {
+            listener.on_progress(&event());
+        }
+    }
+}
+
+/// Combines the progress of the partial uploads of `Client::upload_parallel` into the progress of the whole file.
+pub(crate) struct PartsProgress<'a> {
+    listener: &'a dyn ProgressListener,
+    started: Instant,
+    total_size: u64,
+    /// The bytes the server confirmed for each part.
+    confirmed: Mutex<Vec<u64>>,
+}
+
+impl<'a> PartsProgress<'a> {
+    pub(crate) fn new(listener: &'a dyn ProgressListener, parts: usize, total_size: u64) -> Self {
+        PartsProgress {
+            listener,
+            started: Instant::now(),
+            total_size,
+            confirmed: Mutex::new(vec![0; parts]),
+        }
+    }
+
+    /// The listener for the part with the given index, which starts at `start` in the file.
+    pub(crate) fn part(&self, index: usize, start: u64) -> PartProgress<'_, 'a> {
+        PartProgress {
+            parts: self,
+            index,
+            start,
+        }
+    }
+
+    pub(crate) fn completed(&self) {
+        self.listener.on_progress(&ProgressEvent::Completed {
+            bytes_uploaded: self.total_size,
+        });
+    }
+
+    fn confirmed(&self, index: usize, bytes_uploaded: u64) {
+        let bytes_uploaded = {
+            let mut confirmed = self.confirmed.lock().unwrap_or_else(|err| err.into_inner());
+            confirmed[index] = bytes_uploaded;
+            confirmed.iter().sum::<u64>()
+        };
+
+        let elapsed = self.started.elapsed().as_secs_f64();
+        self.listener.on_progress(&ProgressEvent::Progress {
+            bytes_uploaded,
+            total_size: Some(self.total_size),
+            bytes_per_second: (elapsed > 0.0 && bytes_uploaded > 0)
+                .then(|| bytes_uploaded as f64 / elapsed),
+        });
+    }
+}
+
+/// Reports the events of one partial upload as events of the whole file.
+pub(crate) struct PartProgress<'p, 'a> {
+    parts: &'p PartsProgress<'a>,
+    index: usize,
+    start: u64,
+}
+
+impl ProgressListener for PartProgress<'_, '_> {
+    fn on_progress(&self, event: &ProgressEvent) {
+        match event {
+            ProgressEvent::ChunkStarted { offset, len } => {
+                self.parts
+                    .listener
+                    .on_progress(&ProgressEvent::ChunkStarted {
+                        offset: self.start + offset,
+                        len: *len,
+                    })
+            }
+            ProgressEvent::Progress { bytes_uploaded, .. } => {
+                self.parts.confirmed(self.index, *bytes_uploaded)
+            }
+            ProgressEvent::Retrying { .. } => self.parts.listener.on_progress(event),
+            // the file is only complete once all parts are concatenated
+            ProgressEvent::Completed { .. } => {}
+        }
+    }
+}
@@ -1,46 +1,133 @@
-use crate::http::{HttpHandler, HttpMethod, HttpRequest, HttpResponse};
+use crate::http::{
+    BodyReader, Headers, HttpBody, HttpMethod, HttpRequest, HttpResponse, SendHttpHandler,
+};
 use crate::Error;
-use reqwest::header::{HeaderMap, HeaderName};
+use futures::channel::mpsc;
+use futures::future::join;
+use futures::{AsyncReadExt, SinkExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH};
 use reqwest::Method;
 use std::collections::HashMap;
+use std::io;
 use std::str::FromStr;
 
-impl HttpHandler for reqwest::Client {
-    async fn handle_request(&self, req: HttpRequest) -> Result<HttpResponse, Error> {
-        let mut headers = HeaderMap::new();
-        for (key, value) in req.headers {
-            headers.insert(HeaderName::from_str(&key).unwrap(), value.parse().unwrap());
-        }
+impl SendHttpHandler for reqwest::Client {
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        let builder = self
+            .request(to_method(&req.method), &req.url)
+            .headers(to_header_map(req.headers)?);
 
-        let mut builder = match req.method {
-            HttpMethod::Head => self.head(&req.url),
-            HttpMethod::Patch => self.patch(&req.url),
-            HttpMethod::Options => self.request(Method::OPTIONS, &req.url),
-            HttpMethod::Post => self.post(&req.url),
-            HttpMethod::Delete => self.delete(&req.url),
+        let response = match req.body {
+            None => builder.send().await,
+            Some(HttpBody::Bytes(body)) => builder.body(Vec::from(body)).send().await,
+            Some(HttpBody::Reader { reader, len }) => {
+                // reqwest needs a `'static` body, so the chunk is passed through a channel while the request is sent
+                let (sender, receiver) = mpsc::channel(1);
+                let builder = builder
+                    .header(CONTENT_LENGTH, len)
+                    .body(reqwest::Body::wrap_stream(receiver));
+                let (streamed, response) =
+                    join(stream_body(reader, len, sender), builder.send()).await;
+                streamed?;
+                response
+            }
         }
-        .headers(headers);
+        .map_err(|err| Error::HttpHandlerError(err.to_string()))?;
 
-        if let Some(body) = req.body {
-            builder = builder.body(Vec::from(body));
-        }
+        Ok(HttpResponse {
+            status_code: response.status().as_u16() as usize,
+            headers: from_header_map(response.headers()),
+        })
+    }
+}
 
-        let response = match builder.send() {
-            Ok(resp) => resp,
-            Err(err) => return Err(Error::HttpHandlerError(err.to_string())),
-        };
+#[cfg(feature = "reqwest-blocking")]
+impl SendHttpHandler for reqwest::blocking::Client {
+    /// Executes the request on the current thread, blocking it until the response is received.
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        let mut builder = self
+            .request(to_method(&req.method), &req.url)
+            .headers(to_header_map(req.headers)?);
 
-        let mut headers = HashMap::new();
-        for (key, value) in response.headers() {
-            headers.insert(
-                key.to_string(),
-                value.to_str().map(String::from).unwrap_or_default(),
-            );
+        // the body of a blocking request needs to be `'static`, so a streamed chunk is read into memory
+        if let Some(body) = req.body {
+            builder = builder.body(body.into_vec().await?);
         }
 
+        let response = builder
+            .send()
+            .map_err(|err| Error::HttpHandlerError(err.to_string()))?;
+
         Ok(HttpResponse {
             status_code: response.status().as_u16() as usize,
-            headers,
+            headers: from_header_map(response.headers()),
         })
     }
 }
+
+/// The size of the pieces a streamed body is sent in.
+const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Sends `len` bytes of `reader` to `sender` piece by piece, so only a few pieces are in memory at once.
+async fn stream_body(
+    reader: &mut dyn BodyReader,
+    len: u64,
+    mut sender: mpsc::Sender<io::Result<Vec<u8>>>,
+) -> Result<(), Error> {
+    let mut reader = reader.take(len);
+    loop {
+        let mut buffer = vec![0; STREAM_BUFFER_SIZE];
+        let read = match reader.read(&mut buffer).await {
+            Ok(0) => return Ok(()),
+            Ok(read) => read,
+            Err(err) => {
+                // aborts the request, instead of letting it end with a short body
+                let _ = sender
+                    .send(Err(io::Error::new(err.kind(), err.to_string())))
+                    .await;
+                return Err(Error::IoError(err));
+            }
+        };
+        buffer.truncate(read);
+
+        // the receiver is gone if the request failed, which `send` reports itself
+        if sender.send(Ok(buffer)).await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+fn to_method(method: &HttpMethod) -> Method {
+    match method {
+        HttpMethod::Head => Method::HEAD,
+        HttpMethod::Patch => Method::PATCH,
+        HttpMethod::Options => Method::OPTIONS,
+        HttpMethod::Post => Method::POST,
+        HttpMethod::Delete => Method::DELETE,
+    }
+}
+
+fn to_header_map(headers: Headers) -> Result<HeaderMap, Error> {
+    let mut header_map = HeaderMap::new();
+    for (key, value) in headers {
+        let name = HeaderName::from_str(&key).map_err(|err| {
+            Error::HttpHandlerError(format!("Invalid header name '{}': {}", key, err))
+        })?;
+        let value = HeaderValue::from_str(&value).map_err(|err| {
+            Error::HttpHandlerError(format!("Invalid value for header '{}': {}", key, err))
+        })?;
+        header_map.insert(name, value);
+    }
+    Ok(header_map)
+}
+
+fn from_header_map(header_map: &HeaderMap) -> Headers {
+    let mut headers = HashMap::new();
+    for (key, value) in header_map {
+        headers.insert(
+            key.to_string(),
+            value.to_str().map(String::from).unwrap_or_default(),
+        );
+    }
+    headers
+}
@@ -0,0 +1,259 @@
+use crate::Error;
+use futures::future::BoxFuture;
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::pin::Pin;
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Used by the `Client` to wait between retries, independent of the async runtime in use.
+/// Implement this trait to use the timer of your async runtime instead of the default `ThreadTimer`.
+pub trait Timer: Send + Sync {
+    /// Returns a future which resolves once `duration` has passed.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// A `Timer` which works with any async runtime, by waiting on a separate thread.
+///
+/// All sleeps are served by a single thread, which is started by the first sleep. A sleep which is dropped before it is over, such as the timeout of a request which completed in time, is removed from that thread right away.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ThreadTimer;
+
+impl Timer for ThreadTimer {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let now = Instant::now();
+        Box::pin(Sleep {
+            // a duration too long to be represented is waited for as long as possible
+            deadline: now
+                .checked_add(duration)
+                .unwrap_or_else(|| now + Duration::from_secs(u32::MAX as u64)),
+            key: None,
+        })
+    }
+}
+
+/// The thread which wakes the sleeps of every `ThreadTimer` once they are over.
+struct TimerThread {
+    sleeps: Mutex<Sleeps>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct Sleeps {
+    wakers: BTreeMap<(Instant, u64), Waker>,
+    next_id: u64,
+}
+
+impl TimerThread {
+    fn get() -> &'static TimerThread {
+        static TIMER_THREAD: OnceLock<TimerThread> = OnceLock::new();
+
+        TIMER_THREAD.get_or_init(|| {
+            thread::Builder::new()
+                .name("tus-client-timer".to_owned())
+                .spawn(|| TimerThread::get().run())
+                .expect("failed to spawn the timer thread");
+            TimerThread {
+                sleeps: Mutex::new(Sleeps::default()),
+                changed: Condvar::new(),
+            }
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Sleeps> {
+        self.sleeps.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn run(&self) {
+        let mut sleeps = self.lock();
+        loop {
+            let now = Instant::now();
+            while let Some(entry) = sleeps.wakers.first_entry() {
+                if entry.key().0 > now {
+                    break;
+                }
+                entry.remove().wake();
+            }
+
+            sleeps = match sleeps.wakers.keys().next() {
+                Some(&(deadline, _)) => {
+                    self.changed
+                        .wait_timeout(sleeps, deadline - now)
+                        .unwrap_or_else(|err| err.into_inner())
+                        .0
+                }
+                None => self
+                    .changed
+                    .wait(sleeps)
+                    .unwrap_or_else(|err| err.into_inner()),
+            };
+        }
+    }
+}
+
+/// The future returned by `ThreadTimer::sleep`, which is registered with the timer thread while it is polled.
+struct Sleep {
+    deadline: Instant,
+    key: Option<(Instant, u64)>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        if Instant::now() >= this.deadline {
+            this.cancel();
+            return Poll::Ready(());
+        }
+
+        let timer_thread = TimerThread::get();
+        let mut sleeps = timer_thread.lock();
+        let key = match this.key {
+            Some(key) => key,
+            None => {
+                sleeps.next_id += 1;
+                (this.deadline, sleeps.next_id)
+            }
+        };
+        this.key = Some(key);
+
+        // the timer thread may have woken and removed the sleep already, in which case it is registered again
+        sleeps.wakers.insert(key, cx.waker().clone());
+        if sleeps.wakers.keys().next() == Some(&key) {
+            timer_thread.changed.notify_one();
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Sleep {
+    fn cancel(&mut self) {
+        if let Some(key) = self.key.take() {
+            TimerThread::get().lock().wakers.remove(&key);
+        }
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Describes if, when and how often the `Client` retries a failed request.
+///
+/// The delay before each retry doubles, starting at `base_delay` and capped at `max_delay`. A random part of each delay, up to the `jitter` fraction, is left out, so clients which failed at the same time don't all retry at the same time.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+    retryable_status_codes: Vec<usize>,
+    retry_handler_errors: bool,
+}
+
+impl RetryPolicy {
+    /// Creates a policy which tries every request up to 5 times, starting with a delay of 1 second between attempts.
+    /// Errors from the HTTP handler and the status codes 423, 500, 502, 503 and 504 are retried.
+    pub fn new() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            jitter: 0.5,
+            retryable_status_codes: vec![423, 500, 502, 503, 504],
+            retry_handler_errors: true,
+        }
+    }
+
+    /// Creates a policy which never retries a request.
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::new()
+        }
+    }
+
+    /// Sets the number of times a request is tried, including the first attempt.
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Sets the upper limit of the delay between retries.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the fraction of each delay which is randomized, between `0.0` and `1.0`.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the status codes which cause a request to be retried.
+    pub fn retryable_status_codes(mut self, status_codes: Vec<usize>) -> Self {
+        self.retryable_status_codes = status_codes;
+        self
+    }
+
+    /// Sets whether errors from the HTTP handler, such as connection failures, and timed out requests cause a request to be retried.
+    pub fn retry_handler_errors(mut self, retry: bool) -> Self {
+        self.retry_handler_errors = retry;
+        self
+    }
+
+    /// The delay before retrying a request which failed `failed_attempts` times in a row.
+    pub fn delay(&self, failed_attempts: usize) -> Duration {
+        let exponent = failed_attempts.saturating_sub(1).min(31) as u32;
+        let delay = self
+            .base_delay
+            .checked_mul(2_u32.pow(exponent))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+
+        delay.mul_f64(1.0 - self.jitter * random_fraction())
+    }
+
+    /// Whether a request which failed `failed_attempts` times in a row, most recently with `error`, should be retried.
+    pub(crate) fn should_retry(&self, error: &Error, failed_attempts: usize) -> bool {
+        if failed_attempts >= self.max_attempts {
+            return false;
+        }
+
+        match error {
+            Error::HttpHandlerError(_) | Error::Timeout => self.retry_handler_errors,
+            Error::UnexpectedStatusCode(status_code) => {
+                self.retryable_status_codes.contains(status_code)
+            }
+            Error::Locked => self.retryable_status_codes.contains(&423),
+            _ => false,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new()
+    }
+}
+
+/// Returns a random number in the range `0.0..1.0`, without depending on a random number generator.
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1_u64 << 53) as f64
+}
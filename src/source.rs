@@ -0,0 +1,420 @@
+use bytes::Bytes;
+use futures::channel::oneshot;
+use futures::future::BoxFuture;
+use futures::lock::Mutex;
+use futures::{ready, AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+use std::fs::File;
+use std::future::Future;
+use std::io;
+use std::io::SeekFrom;
+use std::mem;
+use std::pin::Pin;
+use std::sync::{mpsc, Arc, OnceLock};
+use std::task::{Context, Poll};
+use std::thread;
+
+/// The trait used by `tus_client::Client` to read the file which is uploaded.
+///
+/// Reads are positioned, so reading doesn't change the source. This lets several uploads read the same source at the same time, such as the partial uploads of `Client::upload_parallel`.
+pub trait UploadSource: Send + Sync {
+    /// The length of the source in bytes.
+    fn len(&self) -> io::Result<u64>;
+
+    fn is_empty(&self) -> io::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Reads bytes starting at `offset` into `buf`, receiving the number of bytes read.
+    ///
+    /// Fewer bytes than fit into `buf` may be read. Reading 0 bytes means that `offset` is at the end of the source.
+    fn read_at(
+        &self,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> impl Future<Output = io::Result<usize>> + Send;
+}
+
+impl UploadSource for [u8] {
+    fn len(&self) -> io::Result<u64> {
+        Ok(<[u8]>::len(self) as u64)
+    }
+
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        let start = offset.min(<[u8]>::len(self) as u64) as usize;
+        let read = buf.len().min(<[u8]>::len(self) - start);
+        buf[..read].copy_from_slice(&self[start..start + read]);
+        Ok(read)
+    }
+}
+
+impl UploadSource for Vec<u8> {
+    fn len(&self) -> io::Result<u64> {
+        UploadSource::len(self.as_slice())
+    }
+
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.as_slice().read_at(offset, buf).await
+    }
+}
+
+impl UploadSource for Bytes {
+    fn len(&self) -> io::Result<u64> {
+        UploadSource::len(self.as_ref())
+    }
+
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.as_ref().read_at(offset, buf).await
+    }
+}
+
+/// Reads the file with positioned reads on a separate thread, so reading doesn't block the thread of the async runtime.
+///
+/// All reads of files are served by a single thread, which is started by the first read. The file is duplicated with `File::try_clone` for every read, and the bytes read are copied into `buf`.
+#[cfg(any(unix, windows))]
+impl UploadSource for File {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        let (sender, receiver) = oneshot::channel();
+        FileReadThread::get().send(FileRead {
+            file: self.try_clone()?,
+            offset,
+            len: buf.len(),
+            result: sender,
+        })?;
+
+        let data = receiver
+            .await
+            .map_err(|_| io::Error::other("the file read thread stopped"))??;
+        buf[..data.len()].copy_from_slice(&data);
+        Ok(data.len())
+    }
+}
+
+/// A read of a `File`, which is sent to the `FileReadThread`.
+#[cfg(any(unix, windows))]
+struct FileRead {
+    file: File,
+    offset: u64,
+    len: usize,
+    result: oneshot::Sender<io::Result<Vec<u8>>>,
+}
+
+/// The thread which serves the reads of every `File` source.
+#[cfg(any(unix, windows))]
+struct FileReadThread {
+    reads: std::sync::Mutex<mpsc::Sender<FileRead>>,
+}
+
+#[cfg(any(unix, windows))]
+impl FileReadThread {
+    fn get() -> &'static FileReadThread {
+        static FILE_READ_THREAD: OnceLock<FileReadThread> = OnceLock::new();
+
+        FILE_READ_THREAD.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel();
+            thread::Builder::new()
+                .name("tus-client-file-read".to_owned())
+                .spawn(move || FileReadThread::run(receiver))
+                .expect("failed to spawn the file read thread");
+            FileReadThread {
+                reads: std::sync::Mutex::new(sender),
+            }
+        })
+    }
+
+    fn send(&self, read: FileRead) -> io::Result<()> {
+        self.reads
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .send(read)
+            .map_err(|_| io::Error::other("the file read thread stopped"))
+    }
+
+    fn run(receiver: mpsc::Receiver<FileRead>) {
+        for read in receiver {
+            let mut data = vec![0; read.len];
+            #[cfg(unix)]
+            let result = std::os::unix::fs::FileExt::read_at(&read.file, &mut data, read.offset);
+            #[cfg(windows)]
+            let result =
+                std::os::windows::fs::FileExt::seek_read(&read.file, &mut data, read.offset);
+
+            // the read isn't needed anymore if the future waiting for it was dropped
+            let _ = read.result.send(result.map(|len| {
+                data.truncate(len);
+                data
+            }));
+        }
+    }
+}
+
+impl<S> UploadSource for &S
+where
+    S: UploadSource + ?Sized,
+{
+    fn len(&self) -> io::Result<u64> {
+        (**self).len()
+    }
+
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        (**self).read_at(offset, buf).await
+    }
+}
+
+impl<S> UploadSource for Arc<S>
+where
+    S: UploadSource + ?Sized,
+{
+    fn len(&self) -> io::Result<u64> {
+        (**self).len()
+    }
+
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        (**self).read_at(offset, buf).await
+    }
+}
+
+/// Exposes the byte range `start..start + len` of another source as a source of its own.
+#[derive(Debug, Clone)]
+pub struct SourceRange<S> {
+    source: S,
+    start: u64,
+    len: u64,
+}
+
+impl<S> SourceRange<S> {
+    pub fn new(source: S, start: u64, len: u64) -> Self {
+        SourceRange { source, start, len }
+    }
+
+    /// Returns the wrapped source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S> UploadSource for SourceRange<S>
+where
+    S: UploadSource,
+{
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.len)
+    }
+
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        if offset >= self.len {
+            return Ok(0);
+        }
+
+        let read = (self.len - offset).min(buf.len() as u64) as usize;
+        self.source
+            .read_at(self.start + offset, &mut buf[..read])
+            .await
+    }
+}
+
+/// Adapts a reader which implements `AsyncRead + AsyncSeek` to an `UploadSource`.
+///
+/// The reader is seeked before every read, so reads are serialized through a lock.
+pub struct ReaderSource<R> {
+    reader: Mutex<R>,
+    len: u64,
+}
+
+impl<R> ReaderSource<R>
+where
+    R: AsyncRead + AsyncSeek + Send + Unpin,
+{
+    /// Creates a source reading from `reader`, whose length is found by seeking to its end.
+    pub async fn new(mut reader: R) -> io::Result<Self> {
+        let len = reader.seek(SeekFrom::End(0)).await?;
+        Ok(ReaderSource {
+            reader: Mutex::new(reader),
+            len,
+        })
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+impl<R> UploadSource for ReaderSource<R>
+where
+    R: AsyncRead + AsyncSeek + Send + Unpin,
+{
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.len)
+    }
+
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        let mut reader = self.reader.lock().await;
+        reader.seek(SeekFrom::Start(offset)).await?;
+        reader.read(buf).await
+    }
+}
+
+/// Fills `buf` with the bytes of `source` starting at `offset`, failing with `io::ErrorKind::UnexpectedEof` if the source ends first.
+pub(crate) async fn read_exact_at<S>(
+    source: &S,
+    mut offset: u64,
+    mut buf: &mut [u8],
+) -> io::Result<()>
+where
+    S: UploadSource + ?Sized,
+{
+    while !buf.is_empty() {
+        match source.read_at(offset, buf).await? {
+            0 => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "the source ended before the end of the chunk",
+                ))
+            }
+            read => {
+                offset += read as u64;
+                buf = &mut buf[read..];
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The most a `ChunkReader` reads from its source at once, which bounds the memory of its buffer.
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Reads the byte range `start..start + len` of a source, so it can be streamed as the body of a request.
+///
+/// The source is read into a buffer of at most `READ_BUFFER_SIZE` bytes, which is reused for every read, since the future reading the source can't borrow the buffer of the caller between polls.
+/// Reading fails with `io::ErrorKind::UnexpectedEof` if the source ends before the range does.
+pub(crate) struct ChunkReader<'a, S: ?Sized> {
+    source: &'a S,
+    start: u64,
+    len: u64,
+    pos: u64,
+    /// The read in progress, which owns the buffer until it is done.
+    pending: Option<BoxFuture<'a, (Vec<u8>, io::Result<usize>)>>,
+    buffer: Vec<u8>,
+    filled: usize,
+    consumed: usize,
+}
+
+impl<'a, S> ChunkReader<'a, S>
+where
+    S: UploadSource + ?Sized,
+{
+    pub(crate) fn new(source: &'a S, start: u64, len: u64) -> Self {
+        ChunkReader {
+            source,
+            start,
+            len,
+            pos: 0,
+            pending: None,
+            buffer: Vec::new(),
+            filled: 0,
+            consumed: 0,
+        }
+    }
+}
+
+impl<S> AsyncRead for ChunkReader<'_, S>
+where
+    S: UploadSource + ?Sized,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        loop {
+            // bytes are only left over if the caller's buffer shrank while the read was in progress
+            if this.consumed < this.filled {
+                let read = (this.filled - this.consumed).min(buf.len());
+                buf[..read].copy_from_slice(&this.buffer[this.consumed..this.consumed + read]);
+                this.consumed += read;
+                this.pos += read as u64;
+                return Poll::Ready(Ok(read));
+            }
+
+            let remaining = (this.len - this.pos)
+                .min(buf.len() as u64)
+                .min(READ_BUFFER_SIZE as u64) as usize;
+            if remaining == 0 {
+                return Poll::Ready(Ok(0));
+            }
+
+            let source = this.source;
+            let offset = this.start + this.pos;
+            let buffer = &mut this.buffer;
+            let pending = this.pending.get_or_insert_with(|| {
+                let mut data = mem::take(buffer);
+                data.resize(remaining, 0);
+                Box::pin(async move {
+                    let result = source.read_at(offset, &mut data).await;
+                    (data, result)
+                })
+            });
+
+            let (data, result) = ready!(pending.as_mut().poll(cx));
+            this.pending = None;
+            this.buffer = data;
+            this.filled = 0;
+            this.consumed = 0;
+
+            let read = result?;
+            if read == 0 {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "the source ended before the end of the chunk",
+                )));
+            }
+            this.filled = read.min(this.buffer.len());
+        }
+    }
+}
+
+impl<S> AsyncSeek for ChunkReader<'_, S>
+where
+    S: UploadSource + ?Sized,
+{
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => offset_by(this.len, offset),
+            SeekFrom::Current(offset) => offset_by(this.pos, offset),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        this.pos = target.min(this.len);
+        this.pending = None;
+        this.filled = 0;
+        this.consumed = 0;
+
+        Poll::Ready(Ok(this.pos))
+    }
+}
+
+fn offset_by(base: u64, offset: i64) -> Option<u64> {
+    if offset >= 0 {
+        base.checked_add(offset as u64)
+    } else {
+        base.checked_sub(offset.unsigned_abs())
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{Error, Metadata};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Identifies a source file, so the upload of that file can be found again, for example after the process restarted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// The path of the file.
+    pub path: PathBuf,
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The time the file was last modified.
+    pub modified: Option<SystemTime>,
+    /// A hash of the contents of the file, to detect changes which don't affect the size and modification time.
+    pub content_hash: Option<String>,
+}
+
+impl Fingerprint {
+    /// Creates the fingerprint of the file at `path`, from its size and modification time.
+    pub fn from_path<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let metadata = fs::metadata(path.as_ref())?;
+
+        Ok(Fingerprint {
+            path: path.as_ref().to_owned(),
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            content_hash: None,
+        })
+    }
+
+    /// Adds a hash of the contents of the file to the fingerprint.
+    pub fn with_content_hash(mut self, content_hash: String) -> Self {
+        self.content_hash = Some(content_hash);
+        self
+    }
+}
+
+/// Describes an upload which was created for a fingerprinted file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredUpload {
+    /// The upload URL of the file.
+    pub url: String,
+    /// The metadata the upload was created with.
+    pub metadata: Metadata,
+}
+
+/// Remembers the upload URLs of files, so uploads can be resumed across processes.
+pub trait UploadStore {
+    /// Get the upload stored for `fingerprint`, if there is one.
+    fn get(&self, fingerprint: &Fingerprint) -> Result<Option<StoredUpload>, Error>;
+    /// Store `upload` for `fingerprint`, replacing any upload stored before.
+    fn set(&self, fingerprint: &Fingerprint, upload: StoredUpload) -> Result<(), Error>;
+    /// Remove the upload stored for `fingerprint`.
+    fn remove(&self, fingerprint: &Fingerprint) -> Result<(), Error>;
+}
+
+/// An `UploadStore` which keeps the uploads in a JSON file.
+#[derive(Debug)]
+pub struct JsonFileStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredEntry {
+    fingerprint: Fingerprint,
+    #[serde(flatten)]
+    upload: StoredUpload,
+}
+
+impl JsonFileStore {
+    /// Creates a store backed by the file at `path`. The file is created once the first upload is stored.
+    pub fn new<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        JsonFileStore {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read_entries(&self) -> Result<Vec<StoredEntry>, Error> {
+        match fs::read(&self.path) {
+            Ok(contents) => Ok(serde_json::from_slice(&contents).map_err(io::Error::from)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Writes the entries to a temporary file first, so a crash while writing doesn't corrupt the store.
+    fn write_entries(&self, entries: &[StoredEntry]) -> Result<(), Error> {
+        let contents = serde_json::to_vec_pretty(entries).map_err(io::Error::from)?;
+        let temp_path = self.path.with_extension("tmp");
+        fs::write(&temp_path, contents)?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    fn update<F>(&self, update: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Vec<StoredEntry>),
+    {
+        let _guard = self.lock.lock().unwrap_or_else(|err| err.into_inner());
+        let mut entries = self.read_entries()?;
+        update(&mut entries);
+        self.write_entries(&entries)
+    }
+}
+
+impl UploadStore for JsonFileStore {
+    fn get(&self, fingerprint: &Fingerprint) -> Result<Option<StoredUpload>, Error> {
+        let _guard = self.lock.lock().unwrap_or_else(|err| err.into_inner());
+        Ok(self
+            .read_entries()?
+            .into_iter()
+            .find(|entry| &entry.fingerprint == fingerprint)
+            .map(|entry| entry.upload))
+    }
+
+    fn set(&self, fingerprint: &Fingerprint, upload: StoredUpload) -> Result<(), Error> {
+        self.update(|entries| {
+            entries.retain(|entry| &entry.fingerprint != fingerprint);
+            entries.push(StoredEntry {
+                fingerprint: fingerprint.clone(),
+                upload,
+            });
+        })
+    }
+
+    fn remove(&self, fingerprint: &Fingerprint) -> Result<(), Error> {
+        self.update(|entries| entries.retain(|entry| &entry.fingerprint != fingerprint))
+    }
+}
@@ -0,0 +1,584 @@
+//! An in-memory tus server, to test code which uses `Client` without a real server.
+//!
+//! ```rust
+//! use futures::executor::block_on;
+//! use tus_client::testing::InMemoryServer;
+//! use tus_client::Client;
+//!
+//! let server = InMemoryServer::new();
+//! let client = Client::new(server.clone());
+//!
+//! let url = block_on(client.create("/files", 5)).unwrap();
+//! block_on(client.upload(&url, b"hello".to_vec())).unwrap();
+//!
+//! assert_eq!(b"hello", &server.upload(&url).unwrap().data[..]);
+//! ```
+use crate::http::{Headers, HttpMethod, HttpRequest, HttpResponse, SendHttpHandler};
+use crate::{headers, ChecksumAlgorithm, Error, HeaderMap, TusExtension};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+const SUPPORTED_CHECKSUM_ALGORITHMS: [ChecksumAlgorithm; 4] = [
+    ChecksumAlgorithm::Sha1,
+    ChecksumAlgorithm::Md5,
+    ChecksumAlgorithm::Sha256,
+    ChecksumAlgorithm::Crc32,
+];
+
+/// A tus server which keeps its uploads in memory, and handles requests of a `Client` directly as an `HttpHandler`.
+///
+/// Clones of an `InMemoryServer` share the same uploads, so a clone can be given to a `Client` while the original is used to inspect the uploads and to inject faults.
+#[derive(Debug, Clone)]
+pub struct InMemoryServer {
+    state: Arc<Mutex<ServerState>>,
+}
+
+#[derive(Debug)]
+struct ServerState {
+    extensions: Vec<TusExtension>,
+    max_size: Option<u64>,
+    expires_after: Option<Duration>,
+    next_id: usize,
+    uploads: HashMap<String, ServerUpload>,
+    faults: VecDeque<Fault>,
+    requests: Vec<RecordedRequest>,
+}
+
+/// Describes an upload on an `InMemoryServer`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerUpload {
+    /// The data received so far.
+    pub data: Vec<u8>,
+    /// The length of the upload, or `None` if its length is deferred.
+    pub length: Option<u64>,
+    /// The raw `Upload-Metadata` header the upload was created with.
+    pub metadata: Option<String>,
+    /// Whether this is a partial upload, which is meant to be concatenated.
+    pub partial: bool,
+    /// The partial uploads this upload was concatenated from, if it is a final upload.
+    pub concatenated_from: Option<Vec<String>>,
+    /// The time after which the upload expires.
+    pub expires: Option<SystemTime>,
+    expired: bool,
+}
+
+impl ServerUpload {
+    /// Whether all data of the upload was received.
+    pub fn is_complete(&self) -> bool {
+        self.length == Some(self.data.len() as u64)
+    }
+}
+
+/// A request received by an `InMemoryServer`.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// The method of the request, after applying `X-HTTP-Method-Override`.
+    pub method: HttpMethod,
+    pub url: String,
+    pub headers: Headers,
+    /// The length of the request body.
+    pub body_len: usize,
+}
+
+/// A fault the `InMemoryServer` injects instead of handling a request normally.
+#[derive(Debug, Clone)]
+pub struct Fault {
+    /// The method of the request to inject the fault into, or `None` to inject it into the next request.
+    pub method: Option<HttpMethod>,
+    pub kind: FaultKind,
+}
+
+impl Fault {
+    /// A fault which is injected into the next request with the given method.
+    pub fn on(method: HttpMethod, kind: FaultKind) -> Self {
+        Fault {
+            method: Some(method),
+            kind,
+        }
+    }
+
+    /// A fault which is injected into the next request.
+    pub fn next(kind: FaultKind) -> Self {
+        Fault { method: None, kind }
+    }
+}
+
+/// Enumerates the faults an `InMemoryServer` can inject.
+#[derive(Debug, Clone)]
+pub enum FaultKind {
+    /// The request fails with `Error::HttpHandlerError`, as if the connection failed. The server doesn't process the request.
+    HandlerError(String),
+    /// The server responds with the given status code without processing the request.
+    Status(usize),
+    /// The server processes the request, but the response is lost and the request fails with `Error::HttpHandlerError`.
+    LostResponse,
+    /// The server stores only the given number of bytes of the body of a `PATCH` request, as if the connection was interrupted.
+    PartialWrite(usize),
+}
+
+impl InMemoryServer {
+    /// Creates a server which supports every extension the `Client` knows about.
+    pub fn new() -> Self {
+        InMemoryServer::with_extensions(vec![
+            TusExtension::Creation,
+            TusExtension::CreationDeferLength,
+            TusExtension::CreationWithUpload,
+            TusExtension::Expiration,
+            TusExtension::Checksum,
+            TusExtension::Termination,
+            TusExtension::Concatenation,
+        ])
+    }
+
+    /// Creates a server which supports only the given extensions.
+    pub fn with_extensions(extensions: Vec<TusExtension>) -> Self {
+        InMemoryServer {
+            state: Arc::new(Mutex::new(ServerState {
+                extensions,
+                max_size: None,
+                expires_after: None,
+                next_id: 0,
+                uploads: HashMap::new(),
+                faults: VecDeque::new(),
+                requests: Vec::new(),
+            })),
+        }
+    }
+
+    /// Sets the maximum size of an upload. Larger uploads are rejected with `413 Request Entity Too Large`.
+    pub fn max_size(self, max_size: u64) -> Self {
+        self.lock().max_size = Some(max_size);
+        self
+    }
+
+    /// Sets how long uploads are kept after they were last modified, if the server supports the expiration extension.
+    pub fn expires_after(self, expires_after: Duration) -> Self {
+        self.lock().expires_after = Some(expires_after);
+        self
+    }
+
+    /// Queues a fault, which is injected into the next matching request. Faults are injected in the order they were queued.
+    pub fn inject_fault(&self, fault: Fault) {
+        self.lock().faults.push_back(fault);
+    }
+
+    /// Lets the upload at `url` expire immediately, so further requests to it are answered with `410 Gone`.
+    pub fn expire(&self, url: &str) {
+        if let Some(upload) = self.lock().uploads.get_mut(url) {
+            upload.expired = true;
+        }
+    }
+
+    /// Get the upload at `url`, if it exists.
+    pub fn upload(&self, url: &str) -> Option<ServerUpload> {
+        self.lock().uploads.get(url).cloned()
+    }
+
+    /// Get the URLs of all uploads on the server.
+    pub fn upload_urls(&self) -> Vec<String> {
+        self.lock().uploads.keys().cloned().collect()
+    }
+
+    /// Get all requests received by the server, in the order they were received.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ServerState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl Default for InMemoryServer {
+    fn default() -> Self {
+        InMemoryServer::new()
+    }
+}
+
+impl SendHttpHandler for InMemoryServer {
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        let body = match req.body {
+            Some(body) => body.into_vec().await?,
+            None => Vec::new(),
+        };
+        let body = &body[..];
+
+        let mut state = self.lock();
+
+        let method = match req.headers.get_by_key(headers::X_HTTP_METHOD_OVERRIDE) {
+            Some(method) if req.method == HttpMethod::Post => parse_method(method)?,
+            _ => req.method,
+        };
+
+        state.requests.push(RecordedRequest {
+            method,
+            url: req.url.clone(),
+            headers: req.headers.clone(),
+            body_len: body.len(),
+        });
+
+        let fault = state
+            .faults
+            .iter()
+            .position(|fault| fault.method.is_none_or(|m| m == method))
+            .and_then(|index| state.faults.remove(index))
+            .map(|fault| fault.kind);
+
+        let (body, lose_response) = match fault {
+            Some(FaultKind::HandlerError(message)) => return Err(Error::HttpHandlerError(message)),
+            Some(FaultKind::Status(status_code)) => return Ok(response(status_code)),
+            Some(FaultKind::PartialWrite(len)) => (&body[..len.min(body.len())], false),
+            Some(FaultKind::LostResponse) => (body, true),
+            None => (body, false),
+        };
+
+        let response = match method {
+            HttpMethod::Options => state.handle_options(),
+            HttpMethod::Post => state.handle_post(&req.url, &req.headers, body),
+            HttpMethod::Head => state.handle_head(&req.url),
+            HttpMethod::Patch => state.handle_patch(&req.url, &req.headers, body),
+            HttpMethod::Delete => state.handle_delete(&req.url),
+        };
+
+        if lose_response {
+            return Err(Error::HttpHandlerError(
+                "The connection was closed before the response was received".to_owned(),
+            ));
+        }
+
+        Ok(response)
+    }
+}
+
+impl ServerState {
+    fn supports(&self, extension: TusExtension) -> bool {
+        self.extensions.contains(&extension)
+    }
+
+    fn handle_options(&self) -> HttpResponse {
+        let mut response = response(204);
+        response
+            .headers
+            .insert(headers::TUS_VERSION.to_owned(), "1.0.0".to_owned());
+        if !self.extensions.is_empty() {
+            response.headers.insert(
+                headers::TUS_EXTENSION.to_owned(),
+                self.extensions
+                    .iter()
+                    .map(extension_name)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+        }
+        if let Some(max_size) = self.max_size {
+            response
+                .headers
+                .insert(headers::TUS_MAX_SIZE.to_owned(), max_size.to_string());
+        }
+        if self.supports(TusExtension::Checksum) {
+            response.headers.insert(
+                headers::TUS_CHECKSUM_ALGORITHM.to_owned(),
+                SUPPORTED_CHECKSUM_ALGORITHMS
+                    .iter()
+                    .map(ChecksumAlgorithm::name)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+        }
+        response
+    }
+
+    fn handle_post(&mut self, url: &str, headers: &Headers, body: &[u8]) -> HttpResponse {
+        if !self.supports(TusExtension::Creation) {
+            return response(405);
+        }
+
+        let concat = headers.get_by_key(headers::UPLOAD_CONCAT);
+        let mut upload = ServerUpload {
+            data: Vec::new(),
+            length: None,
+            metadata: headers.get_by_key(headers::UPLOAD_METADATA).cloned(),
+            partial: concat.is_some_and(|concat| concat == "partial"),
+            concatenated_from: None,
+            expires: None,
+            expired: false,
+        };
+
+        if let Some(concat) = concat.filter(|concat| concat.starts_with("final;")) {
+            if !self.supports(TusExtension::Concatenation) {
+                return response(400);
+            }
+            let partial_urls: Vec<String> = concat["final;".len()..]
+                .split_whitespace()
+                .map(String::from)
+                .collect();
+            for partial_url in &partial_urls {
+                match self.uploads.get(partial_url) {
+                    Some(partial) if partial.partial && partial.is_complete() => {
+                        upload.data.extend_from_slice(&partial.data)
+                    }
+                    _ => return response(400),
+                }
+            }
+            upload.length = Some(upload.data.len() as u64);
+            upload.concatenated_from = Some(partial_urls);
+        } else if upload.partial && !self.supports(TusExtension::Concatenation) {
+            return response(400);
+        } else if let Some(length) = headers.get_by_key(headers::UPLOAD_LENGTH) {
+            match length.parse() {
+                Ok(length) => upload.length = Some(length),
+                Err(_) => return response(400),
+            }
+        } else if headers
+            .get_by_key(headers::UPLOAD_DEFER_LENGTH)
+            .map(String::as_str)
+            != Some("1")
+            || !self.supports(TusExtension::CreationDeferLength)
+        {
+            return response(400);
+        }
+
+        if let (Some(length), Some(max_size)) = (upload.length, self.max_size) {
+            if length > max_size {
+                return response(413);
+            }
+        }
+
+        let location = format!("{}/{}", url.trim_end_matches('/'), self.next_id);
+        self.next_id += 1;
+        self.uploads.insert(location.clone(), upload);
+
+        let mut response = if body.is_empty() {
+            response(201)
+        } else {
+            if !self.supports(TusExtension::CreationWithUpload) {
+                self.uploads.remove(&location);
+                return response(400);
+            }
+            // the data is handled like a `PATCH` request at offset 0
+            let mut patch_headers = headers.clone();
+            patch_headers.retain(|key, _| key.to_lowercase() != headers::UPLOAD_LENGTH);
+            patch_headers.insert(headers::UPLOAD_OFFSET.to_owned(), "0".to_owned());
+            let patch_response = self.handle_patch(&location, &patch_headers, body);
+            if patch_response.status_code != 204 {
+                self.uploads.remove(&location);
+                return patch_response;
+            }
+            let mut created = response(201);
+            created.headers = patch_response.headers;
+            created
+        };
+
+        self.touch(&location, &mut response);
+        response
+            .headers
+            .insert(headers::LOCATION.to_owned(), location);
+        response
+    }
+
+    fn handle_head(&self, url: &str) -> HttpResponse {
+        let upload = match self.find_upload(url) {
+            Ok(upload) => upload,
+            Err(response) => return response,
+        };
+
+        let mut response = response(200);
+        response
+            .headers
+            .insert("cache-control".to_owned(), "no-store".to_owned());
+        response.headers.insert(
+            headers::UPLOAD_OFFSET.to_owned(),
+            upload.data.len().to_string(),
+        );
+        match upload.length {
+            Some(length) => response
+                .headers
+                .insert(headers::UPLOAD_LENGTH.to_owned(), length.to_string()),
+            None => response
+                .headers
+                .insert(headers::UPLOAD_DEFER_LENGTH.to_owned(), "1".to_owned()),
+        };
+        if let Some(metadata) = &upload.metadata {
+            response
+                .headers
+                .insert(headers::UPLOAD_METADATA.to_owned(), metadata.clone());
+        }
+        if upload.partial {
+            response
+                .headers
+                .insert(headers::UPLOAD_CONCAT.to_owned(), "partial".to_owned());
+        }
+        if let Some(partial_urls) = &upload.concatenated_from {
+            response.headers.insert(
+                headers::UPLOAD_CONCAT.to_owned(),
+                format!("final;{}", partial_urls.join(" ")),
+            );
+        }
+        if let Some(expires) = upload.expires {
+            response.headers.insert(
+                headers::UPLOAD_EXPIRES.to_owned(),
+                httpdate::fmt_http_date(expires),
+            );
+        }
+        response
+    }
+
+    fn handle_patch(&mut self, url: &str, headers: &Headers, body: &[u8]) -> HttpResponse {
+        let upload = match self.find_upload(url) {
+            Ok(upload) => upload,
+            Err(response) => return response,
+        };
+
+        if upload.concatenated_from.is_some() {
+            return response(403);
+        }
+
+        if headers
+            .get_by_key(headers::CONTENT_TYPE)
+            .map(String::as_str)
+            != Some("application/offset+octet-stream")
+        {
+            return response(415);
+        }
+
+        let offset = match headers
+            .get_by_key(headers::UPLOAD_OFFSET)
+            .and_then(|offset| offset.parse::<u64>().ok())
+        {
+            Some(offset) => offset,
+            None => return response(400),
+        };
+        if offset != upload.data.len() as u64 {
+            return response(409);
+        }
+
+        let length = match (upload.length, headers.get_by_key(headers::UPLOAD_LENGTH)) {
+            (None, Some(length)) => match length.parse::<u64>() {
+                Ok(length) => Some(length),
+                Err(_) => return response(400),
+            },
+            // repeating the known length is harmless, changing it isn't
+            (Some(known), Some(length)) if length.parse::<u64>() == Ok(known) => Some(known),
+            (Some(_), Some(_)) => return response(400),
+            (length, None) => length,
+        };
+        if let Some(length) = length {
+            if offset + body.len() as u64 > length || self.max_size.is_some_and(|max| length > max)
+            {
+                return response(413);
+            }
+        }
+
+        if let Some(checksum) = headers.get_by_key(headers::UPLOAD_CHECKSUM) {
+            let mut parts = checksum.splitn(2, ' ');
+            let algorithm = parts
+                .next()
+                .and_then(|name| name.parse::<ChecksumAlgorithm>().ok());
+            let expected = parts.next().and_then(|value| STANDARD.decode(value).ok());
+            match (algorithm, expected) {
+                (Some(algorithm), Some(expected)) if self.supports(TusExtension::Checksum) => {
+                    if algorithm.checksum(body) != expected {
+                        return response(460);
+                    }
+                }
+                _ => return response(400),
+            }
+        }
+
+        let upload = self.uploads.get_mut(url).unwrap();
+        upload.length = length;
+        upload.data.extend_from_slice(body);
+        let offset = upload.data.len();
+
+        let mut response = response(204);
+        response
+            .headers
+            .insert(headers::UPLOAD_OFFSET.to_owned(), offset.to_string());
+        self.touch(url, &mut response);
+        response
+    }
+
+    fn handle_delete(&mut self, url: &str) -> HttpResponse {
+        if !self.supports(TusExtension::Termination) {
+            return response(405);
+        }
+
+        if let Err(response) = self.find_upload(url) {
+            return response;
+        }
+
+        self.uploads.remove(url);
+        response(204)
+    }
+
+    fn find_upload(&self, url: &str) -> Result<&ServerUpload, HttpResponse> {
+        match self.uploads.get(url) {
+            None => Err(response(404)),
+            Some(upload)
+                if upload.expired || upload.expires.is_some_and(|e| e <= SystemTime::now()) =>
+            {
+                Err(response(410))
+            }
+            Some(upload) => Ok(upload),
+        }
+    }
+
+    /// Renews the expiration time of an upload which was modified, and adds it to the response.
+    fn touch(&mut self, url: &str, response: &mut HttpResponse) {
+        let expires_after = match self.expires_after {
+            Some(expires_after) if self.supports(TusExtension::Expiration) => expires_after,
+            _ => return,
+        };
+
+        if let Some(upload) = self.uploads.get_mut(url) {
+            if upload.is_complete() {
+                upload.expires = None;
+                return;
+            }
+            let expires = SystemTime::now() + expires_after;
+            upload.expires = Some(expires);
+            response.headers.insert(
+                headers::UPLOAD_EXPIRES.to_owned(),
+                httpdate::fmt_http_date(expires),
+            );
+        }
+    }
+}
+
+fn response(status_code: usize) -> HttpResponse {
+    let mut headers = Headers::new();
+    headers.insert(headers::TUS_RESUMABLE.to_owned(), "1.0.0".to_owned());
+    HttpResponse {
+        status_code,
+        headers,
+    }
+}
+
+fn parse_method(method: &str) -> Result<HttpMethod, Error> {
+    match method.to_uppercase().as_str() {
+        "HEAD" => Ok(HttpMethod::Head),
+        "PATCH" => Ok(HttpMethod::Patch),
+        "OPTIONS" => Ok(HttpMethod::Options),
+        "POST" => Ok(HttpMethod::Post),
+        "DELETE" => Ok(HttpMethod::Delete),
+        _ => Err(Error::HttpHandlerError(format!(
+            "Unsupported method override '{}'",
+            method
+        ))),
+    }
+}
+
+fn extension_name(extension: &TusExtension) -> &'static str {
+    match extension {
+        TusExtension::Creation => "creation",
+        TusExtension::CreationDeferLength => "creation-defer-length",
+        TusExtension::CreationWithUpload => "creation-with-upload",
+        TusExtension::Expiration => "expiration",
+        TusExtension::Checksum => "checksum",
+        TusExtension::Termination => "termination",
+        TusExtension::Concatenation => "concatenation",
+    }
+}
@@ -0,0 +1,131 @@
+use crate::http::{Headers, HttpMethod, HttpRequest, HttpResponse, SendHttpHandler};
+use crate::Error;
+use bytes::Bytes;
+use futures::future::poll_fn;
+use http::header::{HeaderName, HeaderValue};
+use http::{Method, Request, Response};
+use std::error::Error as StdError;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tower_service::Service;
+
+/// Sends requests through any `tower::Service` which takes an `http::Request<Bytes>` of the `http` 1.x crate, such as a hyper 1.x client, a stack of tower middleware or an axum 0.7 or later router.
+///
+/// The service is cloned for every request, as is usual for tower services, so it should be cheap to clone. The service only needs to be `Send`, since it is kept behind a lock, which is held while it is cloned. The body of the request is read into memory before it is sent, since the service takes `Bytes`.
+/// The body of the response is dropped without being read, since tus responses carry all information in their status and headers. Clients which only reuse a connection once the body of its response was read, such as pooling hyper clients, may not reuse the connection of such a response.
+#[derive(Debug)]
+pub struct ServiceHandler<S> {
+    service: Mutex<S>,
+}
+
+impl<S> ServiceHandler<S> {
+    pub fn new(service: S) -> Self {
+        ServiceHandler {
+            service: Mutex::new(service),
+        }
+    }
+
+    /// Returns the wrapped service.
+    pub fn into_inner(self) -> S {
+        self.service
+            .into_inner()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<S> ServiceHandler<S>
+where
+    S: Clone,
+{
+    fn clone_service(&self) -> S {
+        self.service
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+}
+
+impl<S> Clone for ServiceHandler<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        ServiceHandler::new(self.clone_service())
+    }
+}
+
+impl<S, B> SendHttpHandler for ServiceHandler<S>
+where
+    S: Service<Request<Bytes>, Response = Response<B>> + Clone + Send,
+    S::Future: Send,
+    S::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        let request = to_request(req).await?;
+
+        let mut service = self.clone_service();
+        poll_fn(|cx| service.poll_ready(cx))
+            .await
+            .map_err(handler_error)?;
+        let response = service.call(request).await.map_err(handler_error)?;
+
+        Ok(HttpResponse {
+            status_code: response.status().as_u16() as usize,
+            headers: from_response(&response),
+        })
+    }
+}
+
+fn to_method(method: &HttpMethod) -> Method {
+    match method {
+        HttpMethod::Head => Method::HEAD,
+        HttpMethod::Patch => Method::PATCH,
+        HttpMethod::Options => Method::OPTIONS,
+        HttpMethod::Post => Method::POST,
+        HttpMethod::Delete => Method::DELETE,
+    }
+}
+
+async fn to_request(req: HttpRequest<'_>) -> Result<Request<Bytes>, Error> {
+    let mut builder = Request::builder()
+        .method(to_method(&req.method))
+        .uri(&req.url);
+
+    for (key, value) in req.headers {
+        let name = HeaderName::from_str(&key).map_err(|err| {
+            Error::HttpHandlerError(format!("Invalid header name '{}': {}", key, err))
+        })?;
+        let value = HeaderValue::from_str(&value).map_err(|err| {
+            Error::HttpHandlerError(format!("Invalid value for header '{}': {}", key, err))
+        })?;
+        builder = builder.header(name, value);
+    }
+
+    // `Request<Bytes>` holds the whole body, so a streamed chunk is read into memory
+    let body = match req.body {
+        Some(body) => Bytes::from(body.into_vec().await?),
+        None => Bytes::new(),
+    };
+
+    builder
+        .body(body)
+        .map_err(|err| Error::HttpHandlerError(format!("Invalid request: {}", err)))
+}
+
+fn from_response<B>(response: &Response<B>) -> Headers {
+    let mut headers = Headers::new();
+    for (key, value) in response.headers() {
+        headers.insert(
+            key.to_string(),
+            value.to_str().map(String::from).unwrap_or_default(),
+        );
+    }
+    headers
+}
+
+fn handler_error<E>(err: E) -> Error
+where
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    Error::HttpHandlerError(err.into().to_string())
+}
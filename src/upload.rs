@@ -0,0 +1,265 @@
+use crate::http::HttpHandler;
+use crate::{Client, Error, Metadata, UploadInfo, UploadResult, UploadSource};
+use futures::future::poll_fn;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Poll, Waker};
+
+/// Describes where an `Upload` is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadState {
+    /// The upload was created, but `Upload::run` wasn't called yet.
+    Created,
+    /// Chunks of the file are being sent to the server.
+    Uploading,
+    /// The upload was paused, and waits for `UploadHandle::resume` before sending the next chunk.
+    Paused,
+    /// The file was uploaded completely.
+    Completed,
+    /// The upload failed. Calling `Upload::run` again resumes it.
+    Failed,
+    /// The upload was cancelled, but not deleted from the server, so it can still be resumed by another upload.
+    Cancelled,
+    /// The upload was cancelled, and deleted from the server because it should be terminated on cancellation.
+    Terminated,
+}
+
+/// An upload session of a single file, which can be paused, resumed and cancelled through its `UploadHandle` while it is running.
+///
+/// Create an `Upload` with `Client::begin_upload`, get a handle to it with `handle`, and run it with `run`.
+pub struct Upload<'a, H, U>
+where
+    H: HttpHandler,
+{
+    client: &'a Client<H>,
+    url: String,
+    source: U,
+    chunk_size: usize,
+    terminate_on_cancel: bool,
+    control: Arc<UploadControl>,
+}
+
+impl<'a, H, U> Upload<'a, H, U>
+where
+    H: HttpHandler,
+    U: UploadSource,
+{
+    pub(crate) fn new(client: &'a Client<H>, url: &str, source: U) -> Self {
+        Upload {
+            client,
+            url: url.to_owned(),
+            source,
+            chunk_size: client.chunk_size(),
+            terminate_on_cancel: false,
+            control: Arc::new(UploadControl::default()),
+        }
+    }
+
+    /// Sets the size of the chunks the file is uploaded in. A chunk size of 0 is treated as 1.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Sets whether the upload is deleted from the server when it is cancelled, using the termination extension.
+    pub fn terminate_on_cancel(mut self, terminate: bool) -> Self {
+        self.terminate_on_cancel = terminate;
+        self
+    }
+
+    /// A handle to pause, resume or cancel the upload, from another task or thread.
+    pub fn handle(&self) -> UploadHandle {
+        UploadHandle {
+            control: self.control.clone(),
+        }
+    }
+
+    /// The upload URL of the file.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The source the file is read from.
+    pub fn source(&self) -> &U {
+        &self.source
+    }
+
+    /// The metadata of the upload, once the upload was started.
+    pub fn metadata(&self) -> Option<Metadata> {
+        self.control.lock().metadata.clone()
+    }
+
+    /// The number of bytes the server confirmed it received.
+    pub fn offset(&self) -> u64 {
+        self.control.offset()
+    }
+
+    /// The state of the upload.
+    pub fn state(&self) -> UploadState {
+        self.control.state()
+    }
+
+    /// Upload the file, resuming the upload from the offset reported by the server.
+    ///
+    /// Once the upload is paused, the chunk being sent is completed and the upload waits until it is resumed.
+    /// Once the upload is cancelled, the chunk being sent is completed and the upload fails with `Error::Cancelled`. If the upload should be terminated on cancellation, it is deleted from the server first.
+    /// An upload which failed can be resumed by calling `run` again.
+    pub async fn run(&mut self) -> Result<UploadResult, Error> {
+        self.control.set_state(UploadState::Uploading);
+
+        let result = self
+            .client
+            .upload_with_listener(
+                &self.url,
+                &self.source,
+                self.chunk_size,
+                None,
+                Some(&self.control),
+            )
+            .await;
+
+        let (state, result) = match result {
+            Ok(result) => (UploadState::Completed, Ok(result)),
+            Err(Error::Cancelled) if self.terminate_on_cancel => {
+                match self.client.delete(&self.url).await {
+                    Ok(()) => (UploadState::Terminated, Err(Error::Cancelled)),
+                    Err(err) => (UploadState::Failed, Err(err)),
+                }
+            }
+            Err(Error::Cancelled) => (UploadState::Cancelled, Err(Error::Cancelled)),
+            Err(err) => (UploadState::Failed, Err(err)),
+        };
+        self.control.set_state(state);
+
+        result
+    }
+}
+
+/// Controls an `Upload`. Handles can be cloned and sent to other tasks or threads.
+#[derive(Debug, Clone)]
+pub struct UploadHandle {
+    control: Arc<UploadControl>,
+}
+
+impl UploadHandle {
+    /// Pauses the upload once the chunk being sent is completed.
+    pub fn pause(&self) {
+        self.control.lock().paused = true;
+    }
+
+    /// Resumes a paused upload.
+    pub fn resume(&self) {
+        let mut session = self.control.lock();
+        session.paused = false;
+        session.wake();
+    }
+
+    /// Stops the upload once the chunk being sent is completed. A paused upload is stopped immediately.
+    pub fn cancel(&self) {
+        let mut session = self.control.lock();
+        session.cancelled = true;
+        session.wake();
+    }
+
+    /// Whether the upload was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.control.lock().cancelled
+    }
+
+    /// The number of bytes the server confirmed it received.
+    pub fn offset(&self) -> u64 {
+        self.control.offset()
+    }
+
+    /// The state of the upload.
+    pub fn state(&self) -> UploadState {
+        self.control.state()
+    }
+}
+
+/// The state shared between an `Upload` and its handles.
+#[derive(Debug, Default)]
+pub(crate) struct UploadControl {
+    session: Mutex<Session>,
+}
+
+#[derive(Debug)]
+struct Session {
+    state: UploadState,
+    offset: u64,
+    metadata: Option<Metadata>,
+    paused: bool,
+    cancelled: bool,
+    waker: Option<Waker>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            state: UploadState::Created,
+            offset: 0,
+            metadata: None,
+            paused: false,
+            cancelled: false,
+            waker: None,
+        }
+    }
+}
+
+impl Session {
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl UploadControl {
+    fn lock(&self) -> MutexGuard<'_, Session> {
+        self.session.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn state(&self) -> UploadState {
+        self.lock().state
+    }
+
+    fn set_state(&self, state: UploadState) {
+        self.lock().state = state;
+    }
+
+    fn offset(&self) -> u64 {
+        self.lock().offset
+    }
+
+    /// Records what the server reported about the upload when it was started.
+    pub(crate) fn started(&self, info: &UploadInfo) {
+        let mut session = self.lock();
+        session.offset = info.bytes_uploaded;
+        session.metadata = info.metadata.clone();
+    }
+
+    /// Records that the server confirmed it received the file up to `offset`.
+    pub(crate) fn confirmed(&self, offset: u64) {
+        self.lock().offset = offset;
+    }
+
+    /// Called before sending each chunk: waits while the upload is paused, and fails with `Error::Cancelled` once it is cancelled.
+    pub(crate) async fn checkpoint(&self) -> Result<(), Error> {
+        poll_fn(|cx| {
+            let mut session = self.lock();
+            if session.cancelled {
+                return Poll::Ready(Err(Error::Cancelled));
+            }
+            if !session.paused {
+                if session.state == UploadState::Paused {
+                    session.state = UploadState::Uploading;
+                }
+                return Poll::Ready(Ok(()));
+            }
+
+            session.state = UploadState::Paused;
+            session.waker = Some(cx.waker().clone());
+            Poll::Pending
+        })
+        .await
+    }
+}
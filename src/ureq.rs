@@ -0,0 +1,68 @@
+use crate::blocking::HttpHandler;
+use crate::http::{BodyReader, Headers, HttpBody, HttpMethod, HttpRequest, HttpResponse};
+use crate::Error;
+use futures::{AsyncReadExt, FutureExt};
+use std::io;
+use std::io::Read;
+
+impl HttpHandler for ureq::Agent {
+    fn handle_request(&self, req: HttpRequest<'_>) -> Result<HttpResponse, Error> {
+        let mut request = self.request(to_method(&req.method), &req.url);
+        for (key, value) in &req.headers {
+            request = request.set(key, value);
+        }
+
+        let result = match req.body {
+            Some(HttpBody::Bytes(body)) => request.send_bytes(body),
+            Some(HttpBody::Reader { reader, len }) => request
+                .set("content-length", &len.to_string())
+                .send(SyncReader(reader).take(len)),
+            None => request.call(),
+        };
+
+        // ureq reports error status codes as errors, but the `Client` needs to see their responses
+        let response = match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(err) => return Err(Error::HttpHandlerError(err.to_string())),
+        };
+
+        Ok(HttpResponse {
+            status_code: response.status() as usize,
+            headers: from_response(&response),
+        })
+    }
+}
+
+/// Reads the body of a request made by the `blocking::Client`, whose readers never have to wait.
+struct SyncReader<'a>(&'a mut dyn BodyReader);
+
+impl Read for SyncReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf).now_or_never().unwrap_or_else(|| {
+            Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "the body of a blocking request can't wait for data",
+            ))
+        })
+    }
+}
+
+fn to_method(method: &HttpMethod) -> &'static str {
+    match method {
+        HttpMethod::Head => "HEAD",
+        HttpMethod::Patch => "PATCH",
+        HttpMethod::Options => "OPTIONS",
+        HttpMethod::Post => "POST",
+        HttpMethod::Delete => "DELETE",
+    }
+}
+
+fn from_response(response: &ureq::Response) -> Headers {
+    let mut headers = Headers::new();
+    for name in response.headers_names() {
+        if let Some(value) = response.header(&name) {
+            headers.insert(name.to_lowercase(), value.to_owned());
+        }
+    }
+    headers
+}
@@ -0,0 +1,216 @@
+use futures::FutureExt;
+use std::io::Cursor;
+use std::time::Duration;
+use tus_client::blocking::{Client, HttpHandler};
+use tus_client::http::{HttpHandler as _, HttpRequest, HttpResponse};
+use tus_client::testing::{Fault, FaultKind, InMemoryServer};
+use tus_client::{ClientBuilder, Error, Metadata, TusExtension};
+
+/// Serves requests from an `InMemoryServer`, which responds without waiting.
+struct BlockingServer(InMemoryServer);
+
+impl HttpHandler for BlockingServer {
+    fn handle_request(&self, req: HttpRequest<'_>) -> Result<HttpResponse, Error> {
+        self.0
+            .handle_request(req)
+            .now_or_never()
+            .expect("the server responds immediately")
+    }
+}
+
+#[test]
+fn should_create_and_upload_file() {
+    let server = InMemoryServer::new();
+    let client = Client::new(BlockingServer(server.clone()));
+
+    let url = client.create("/files", 11).expect("'create' call failed");
+    let result = client
+        .upload(&url, Cursor::new(b"hello world"))
+        .expect("'upload' call failed");
+
+    assert_eq!(11, result.bytes_uploaded);
+    assert_eq!(b"hello world", &server.upload(&url).unwrap().data[..]);
+}
+
+#[test]
+fn should_upload_in_chunks_of_builder() {
+    let server = InMemoryServer::new();
+    let client = ClientBuilder::new()
+        .chunk_size(4)
+        .build_blocking(BlockingServer(server.clone()));
+    let url = client.create("/files", 11).unwrap();
+
+    client.upload(&url, Cursor::new(b"hello world")).unwrap();
+
+    assert_eq!(b"hello world", &server.upload(&url).unwrap().data[..]);
+    assert_eq!(
+        3,
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.method == tus_client::http::HttpMethod::Patch)
+            .count()
+    );
+}
+
+#[test]
+fn should_get_info_with_metadata() {
+    let server = InMemoryServer::new();
+    let client = Client::new(BlockingServer(server));
+    let mut metadata = Metadata::new();
+    metadata.insert("filename", "file.txt").unwrap();
+
+    let url = client
+        .create_with_metadata("/files", 5, metadata.clone())
+        .unwrap();
+    client
+        .upload_with_chunk_size(&url, Cursor::new(b"hello"), 2)
+        .expect("'upload_with_chunk_size' call failed");
+    let info = client.get_info(&url).expect("'get_info' call failed");
+
+    assert_eq!(5, info.bytes_uploaded);
+    assert_eq!(Some(5), info.total_size);
+    assert_eq!(Some(metadata), info.metadata);
+}
+
+#[test]
+fn should_get_server_info() {
+    let server = InMemoryServer::with_extensions(vec![TusExtension::Creation]);
+    let client = Client::new(BlockingServer(server));
+
+    let info = client.get_server_info("/files").unwrap();
+
+    assert_eq!(vec![TusExtension::Creation], info.extensions);
+}
+
+#[test]
+fn should_delete_file() {
+    let server = InMemoryServer::new();
+    let client = Client::new(BlockingServer(server.clone()));
+    let url = client.create("/files", 3).unwrap();
+
+    client.delete(&url).expect("'delete' call failed");
+
+    assert!(server.upload(&url).is_none());
+    assert!(matches!(client.get_info(&url), Err(Error::NotFoundError)));
+}
+
+#[test]
+fn should_report_handler_errors() {
+    let server = InMemoryServer::new();
+    let client = Client::new(BlockingServer(server.clone()));
+    server.inject_fault(Fault::next(FaultKind::HandlerError("reset".to_owned())));
+
+    match client.create("/files", 3) {
+        Err(Error::HttpHandlerError(message)) => assert_eq!("reset", message),
+        result => panic!("Expected 'Error::HttpHandlerError', got {:?}", result),
+    }
+}
+
+#[cfg(feature = "ureq")]
+#[test]
+fn should_send_request_with_ureq() {
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use tus_client::http::HttpMethod;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/files/1", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 1024];
+        let len = stream.read(&mut request).unwrap();
+        stream
+            .write_all(
+                b"HTTP/1.1 404 Not Found\r\nTus-Resumable: 1.0.0\r\nContent-Length: 0\r\n\r\n",
+            )
+            .unwrap();
+        String::from_utf8_lossy(&request[..len]).to_lowercase()
+    });
+
+    let mut headers = HashMap::new();
+    headers.insert("tus-resumable".to_owned(), "1.0.0".to_owned());
+    let response = ureq::Agent::new()
+        .handle_request(HttpRequest {
+            method: HttpMethod::Head,
+            headers,
+            url,
+            body: None,
+        })
+        .expect("'handle_request' call failed");
+
+    assert_eq!(404, response.status_code);
+    assert_eq!("1.0.0", response.headers["tus-resumable"]);
+    let request = server.join().unwrap();
+    assert!(request.starts_with("head /files/1 "));
+    assert!(request.contains("tus-resumable: 1.0.0"));
+}
+
+#[cfg(feature = "ureq")]
+#[test]
+fn should_stream_reader_body_with_ureq() {
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use tus_client::http::{HttpBody, HttpMethod};
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/files/1", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        while !request.ends_with(b"hello world") {
+            let mut buffer = [0; 1024];
+            let len = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..len]);
+        }
+        stream
+            .write_all(b"HTTP/1.1 204 No Content\r\nUpload-Offset: 11\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        String::from_utf8(request).unwrap().to_lowercase()
+    });
+
+    let mut reader = futures::io::Cursor::new(b"hello world".to_vec());
+    let response = ureq::Agent::new()
+        .handle_request(HttpRequest {
+            method: HttpMethod::Patch,
+            headers: HashMap::new(),
+            url,
+            body: Some(HttpBody::Reader {
+                reader: &mut reader,
+                len: 11,
+            }),
+        })
+        .expect("'handle_request' call failed");
+
+    assert_eq!(204, response.status_code);
+    let request = server.join().unwrap();
+    assert!(request.contains("content-length: 11\r\n"));
+    assert!(!request.contains("transfer-encoding"));
+}
+
+/// Takes longer to respond than the request timeout of the client.
+struct SlowServer(InMemoryServer);
+
+impl HttpHandler for SlowServer {
+    fn handle_request(&self, req: HttpRequest<'_>) -> Result<HttpResponse, Error> {
+        std::thread::sleep(Duration::from_millis(20));
+        BlockingServer(self.0.clone()).handle_request(req)
+    }
+}
+
+#[test]
+fn should_ignore_request_timeout() {
+    let server = InMemoryServer::new();
+    let client = ClientBuilder::new()
+        .request_timeout(Duration::from_millis(1))
+        .build_blocking(SlowServer(server.clone()));
+
+    let url = client.create("/files", 11).expect("'create' call failed");
+    client
+        .upload(&url, Cursor::new(b"hello world"))
+        .expect("'upload' call failed");
+
+    assert_eq!(b"hello world", &server.upload(&url).unwrap().data[..]);
+}
@@ -1,22 +1,38 @@
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
+use futures::future::BoxFuture;
 use futures::io::Cursor;
-use futures::{AsyncRead, AsyncSeek, AsyncSeekExt};
+use futures::StreamExt;
 use std::collections::HashMap;
 use std::future::Future;
-use std::io::SeekFrom;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 use std::task::Poll;
-use tus_client;
-use tus_client::http::{HttpHandler, HttpMethod, HttpRequest, HttpResponse};
-use tus_client::{Error, TusExtension};
+use std::time::{Duration, SystemTime};
+use tus_client::http::{
+    DynHttpHandler, HeadersLayer, HttpBody, HttpHandler, HttpHandlerExt, HttpMethod, HttpRequest,
+    HttpResponse,
+};
+use tus_client::testing::{Fault, FaultKind, InMemoryServer};
+use tus_client::{
+    progress_channel, ChecksumAlgorithm, ClientBuilder, Error, Fingerprint, JsonFileStore,
+    Metadata, MethodOverride, ProgressEvent, RetryPolicy, StoredUpload, ThreadTimer, Timer,
+    TokenProvider, TusExtension, UploadHandle, UploadSource, UploadState, UploadStore,
+};
 
 struct TestHandler {
-    pub upload_progress: usize,
-    pub total_upload_size: usize,
+    pub upload_progress: u64,
+    pub total_upload_size: u64,
     pub status_code: usize,
     pub tus_version: String,
     pub extensions: String,
-    pub max_upload_size: usize,
+    pub max_upload_size: u64,
+    pub checksum_algorithms: String,
+    pub checksum_mismatches: AtomicUsize,
+    pub patch_requests: Arc<Mutex<Vec<HashMap<String, String>>>>,
+    pub post_requests: Arc<Mutex<Vec<HashMap<String, String>>>>,
 }
 
 impl Default for TestHandler {
@@ -28,6 +44,10 @@ impl Default for TestHandler {
             tus_version: String::from("1.0.0"),
             extensions: String::from(""),
             max_upload_size: 12345,
+            checksum_algorithms: String::from(""),
+            checksum_mismatches: AtomicUsize::new(0),
+            patch_requests: Arc::new(Mutex::new(Vec::new())),
+            post_requests: Arc::new(Mutex::new(Vec::new())),
         }
     }
 }
@@ -58,7 +78,11 @@ impl HttpHandler for TestHandler {
                 headers.insert("upload-offset".to_owned(), self.upload_progress.to_string());
                 headers.insert(
                     "upload-metadata".to_owned(),
-                    STANDARD.encode("key_one:value_one;key_two:value_two;k"),
+                    format!(
+                        "key_one {},key_two {},k",
+                        STANDARD.encode("value_one"),
+                        STANDARD.encode("value_two")
+                    ),
                 );
 
                 Ok(HttpResponse {
@@ -71,6 +95,10 @@ impl HttpHandler for TestHandler {
                 headers.insert("tus-version".to_owned(), self.tus_version.clone());
                 headers.insert("tus-extension".to_owned(), self.extensions.clone());
                 headers.insert("tus-max-size".to_owned(), self.max_upload_size.to_string());
+                headers.insert(
+                    "tus-checksum-algorithm".to_owned(),
+                    self.checksum_algorithms.clone(),
+                );
 
                 Ok(HttpResponse {
                     status_code: self.status_code,
@@ -78,6 +106,23 @@ impl HttpHandler for TestHandler {
                 })
             }
             HttpMethod::Patch => {
+                self.patch_requests
+                    .lock()
+                    .unwrap()
+                    .push(req.headers.clone());
+
+                if req.headers.contains_key("upload-checksum")
+                    && self
+                        .checksum_mismatches
+                        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                        .is_ok()
+                {
+                    return Ok(HttpResponse {
+                        status_code: 460,
+                        headers: HashMap::new(),
+                    });
+                }
+
                 let mut headers = HashMap::new();
                 headers.insert("tus-version".to_owned(), self.tus_version.clone());
                 headers.insert(
@@ -87,7 +132,7 @@ impl HttpHandler for TestHandler {
                             .headers
                             .get("upload-offset")
                             .unwrap()
-                            .parse::<usize>()
+                            .parse::<u64>()
                             .unwrap())
                     .to_string(),
                 );
@@ -98,6 +143,8 @@ impl HttpHandler for TestHandler {
                 })
             }
             HttpMethod::Post => {
+                self.post_requests.lock().unwrap().push(req.headers.clone());
+
                 let mut headers = HashMap::new();
                 headers.insert("tus-version".to_owned(), self.tus_version.clone());
                 headers.insert("location".to_owned(), "/something_else".to_owned());
@@ -120,9 +167,8 @@ impl HttpHandler for TestHandler {
     }
 }
 
-fn create_temp_file() -> impl AsyncRead + AsyncSeek + Unpin {
-    let buffer: Vec<u8> = (0..(1024 * 763)).map(|_| rand::random::<u8>()).collect();
-    Cursor::new(buffer)
+fn create_temp_file() -> Vec<u8> {
+    (0..(1024 * 763)).map(|_| rand::random::<u8>()).collect()
 }
 
 #[test]
@@ -137,14 +183,150 @@ fn should_report_correct_upload_progress() {
     let metadata = info.metadata.unwrap();
     assert_eq!(1234, info.bytes_uploaded);
     assert_eq!(2345, info.total_size.unwrap());
-    assert_eq!(
-        String::from("value_one"),
-        metadata.get("key_one").unwrap().to_owned()
-    );
-    assert_eq!(
-        String::from("value_two"),
-        metadata.get("key_two").unwrap().to_owned()
-    );
+    assert_eq!(Some("value_one"), metadata.get_str("key_one"));
+    assert_eq!(Some("value_two"), metadata.get_str("key_two"));
+    assert_eq!(Some(&b""[..]), metadata.get("k"));
+}
+
+#[test]
+fn should_round_trip_metadata() {
+    let server = InMemoryServer::new();
+    let client = tus_client::Client::new(server.clone());
+
+    let mut metadata = Metadata::new();
+    metadata
+        .insert("filename", "world_domination_plan.pdf")
+        .unwrap();
+    metadata
+        .insert("thumbnail", vec![0xff, 0x00, 0xfe])
+        .unwrap();
+    metadata.insert("is_confidential", "").unwrap();
+
+    let url = unwrap_future(client.create_with_metadata("/files", 10, metadata.clone()))
+        .expect("'create_with_metadata' call failed");
+    let info = unwrap_future(client.get_info(&url)).expect("'get_info' call failed");
+
+    assert_eq!(Some(metadata), info.metadata);
+    assert!(server
+        .upload(&url)
+        .unwrap()
+        .metadata
+        .unwrap()
+        .contains("is_confidential,"));
+}
+
+#[test]
+fn should_reject_invalid_metadata_keys() {
+    let mut metadata = Metadata::new();
+
+    for key in ["", "two words", "a,b", "tab\t", "sch\u{f6}n"] {
+        match metadata.insert(key, "value") {
+            Err(Error::InvalidMetadataKey(invalid)) => assert_eq!(key, invalid),
+            result => panic!("Expected 'Error::InvalidMetadataKey', got {:?}", result),
+        }
+    }
+    assert!(metadata.is_empty());
+}
+
+#[test]
+fn should_reject_metadata_with_invalid_base64() {
+    let metadata = "filename d29ybGQ=,broken !!!".parse::<Metadata>();
+
+    match metadata {
+        Err(Error::InvalidHeader(header)) => assert_eq!("upload-metadata", header),
+        result => panic!("Expected 'Error::InvalidHeader', got {:?}", result),
+    }
+}
+
+/// Answers `HEAD` requests with metadata written by another client, including a key this client wouldn't send and a value which isn't base64.
+struct ForeignMetadata(InMemoryServer);
+
+impl HttpHandler for ForeignMetadata {
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        let method = req.method;
+        let mut response = self.0.handle_request(req).await?;
+        if method == HttpMethod::Head {
+            response.headers.insert(
+                "upload-metadata".to_owned(),
+                "gr\u{f6}\u{df}e MTA=,filename d29ybGQ=,broken !!!".to_owned(),
+            );
+        }
+        Ok(response)
+    }
+}
+
+#[test]
+fn should_resume_upload_with_foreign_metadata() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    server.inject_fault(Fault::on(HttpMethod::Patch, FaultKind::LostResponse));
+    let client = tus_client::Client::new(ForeignMetadata(server.clone()));
+
+    let result = unwrap_future(client.upload_with_chunk_size(&url, &buffer, 100 * 1024));
+    assert!(result.is_err());
+    let info = unwrap_future(client.get_info(&url)).expect("'get_info' call failed");
+    let metadata = info.metadata.unwrap();
+    assert_eq!(100 * 1024, info.bytes_uploaded);
+    assert_eq!(Some("10"), metadata.get_str("gr\u{f6}\u{df}e"));
+    assert_eq!(Some("world"), metadata.get_str("filename"));
+    assert!(!metadata.contains_key("broken"));
+
+    unwrap_future(client.upload(&url, &buffer)).expect("'upload' call failed");
+
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+}
+
+#[test]
+fn should_report_sizes_larger_than_4_gib() {
+    let client = tus_client::Client::new(TestHandler {
+        upload_progress: 5 * 1024 * 1024 * 1024,
+        total_upload_size: 6 * 1024 * 1024 * 1024,
+        status_code: 204,
+        ..TestHandler::default()
+    });
+
+    let info = unwrap_future(client.get_info("/something")).expect("'get_info' call failed");
+
+    assert_eq!(5_368_709_120, info.bytes_uploaded);
+    assert_eq!(Some(6_442_450_944), info.total_size);
+}
+
+/// Responds to every `HEAD` request with the given `Upload-Offset` header value.
+struct RawOffsetHandler(&'static str);
+
+impl HttpHandler for RawOffsetHandler {
+    async fn handle_request<'a>(&self, _req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        let mut headers = HashMap::new();
+        headers.insert("upload-offset".to_owned(), self.0.to_owned());
+
+        Ok(HttpResponse {
+            status_code: 200,
+            headers,
+        })
+    }
+}
+
+#[test]
+fn should_reject_invalid_upload_offset() {
+    for offset in ["-1", "+5", " 5", "5.0", ""] {
+        let client = tus_client::Client::new(RawOffsetHandler(offset));
+
+        match unwrap_future(client.get_info("/something")) {
+            Err(Error::InvalidHeader(header)) => assert_eq!("upload-offset", header),
+            result => panic!("Expected 'Error::InvalidHeader', got {:?}", result),
+        }
+    }
+}
+
+#[test]
+fn should_reject_upload_offset_overflowing_u64() {
+    let client = tus_client::Client::new(RawOffsetHandler("18446744073709551616"));
+
+    match unwrap_future(client.get_info("/something")) {
+        Err(Error::ParsingError(_)) => {}
+        result => panic!("Expected 'Error::ParsingError', got {:?}", result),
+    }
 }
 
 #[test]
@@ -185,11 +367,11 @@ fn should_return_server_info() {
 
 #[test]
 fn should_upload_file() {
-    let mut temp_file = create_temp_file();
+    let temp_file = create_temp_file();
 
     let client = tus_client::Client::new(TestHandler {
         upload_progress: 0,
-        total_upload_size: unwrap_future(temp_file.seek(SeekFrom::End(0))).unwrap() as usize,
+        total_upload_size: temp_file.len() as u64,
         status_code: 204,
         ..TestHandler::default()
     });
@@ -199,11 +381,11 @@ fn should_upload_file() {
 
 #[test]
 fn should_upload_file_with_custom_chunk_size() {
-    let mut temp_file = create_temp_file();
+    let temp_file = create_temp_file();
 
     let client = tus_client::Client::new(TestHandler {
         upload_progress: 0,
-        total_upload_size: unwrap_future(temp_file.seek(SeekFrom::End(0))).unwrap() as usize,
+        total_upload_size: temp_file.len() as u64,
         status_code: 204,
         ..TestHandler::default()
     });
@@ -214,45 +396,39 @@ fn should_upload_file_with_custom_chunk_size() {
 
 #[test]
 fn should_receive_upload_path() {
-    let mut temp_file = create_temp_file();
+    let temp_file = create_temp_file();
 
     let client = tus_client::Client::new(TestHandler {
         status_code: 201,
         ..TestHandler::default()
     });
 
-    let mut metadata = HashMap::new();
-    metadata.insert("key_one".to_owned(), "value_one".to_owned());
-    metadata.insert("key_two".to_owned(), "value_two".to_owned());
+    let mut metadata = Metadata::new();
+    metadata.insert("key_one", "value_one").unwrap();
+    metadata.insert("key_two", "value_two").unwrap();
 
-    let result = unwrap_future(client.create(
-        "/something",
-        unwrap_future(temp_file.seek(SeekFrom::End(0))).unwrap() as usize,
-    ))
-    .expect("'create_with_metadata' call failed");
+    let result = unwrap_future(client.create("/something", temp_file.len() as u64))
+        .expect("'create_with_metadata' call failed");
 
     assert!(!result.is_empty());
 }
 
 #[test]
 fn should_receive_upload_path_with_metadata() {
-    let mut temp_file = create_temp_file();
+    let temp_file = create_temp_file();
 
     let client = tus_client::Client::new(TestHandler {
         status_code: 201,
         ..TestHandler::default()
     });
 
-    let mut metadata = HashMap::new();
-    metadata.insert("key_one".to_owned(), "value_one".to_owned());
-    metadata.insert("key_two".to_owned(), "value_two".to_owned());
+    let mut metadata = Metadata::new();
+    metadata.insert("key_one", "value_one").unwrap();
+    metadata.insert("key_two", "value_two").unwrap();
 
-    let result = unwrap_future(client.create_with_metadata(
-        "/something",
-        unwrap_future(temp_file.seek(SeekFrom::End(0))).unwrap() as usize,
-        metadata,
-    ))
-    .expect("'create_with_metadata' call failed");
+    let result =
+        unwrap_future(client.create_with_metadata("/something", temp_file.len() as u64, metadata))
+            .expect("'create_with_metadata' call failed");
 
     assert!(!result.is_empty());
 }
@@ -266,3 +442,2068 @@ fn should_receive_204_after_deleting_file() {
 
     unwrap_future(client.delete("/something")).expect("'delete' call failed");
 }
+
+#[test]
+fn should_return_checksum_algorithms_in_server_info() {
+    let client = tus_client::Client::new(TestHandler {
+        status_code: 204,
+        extensions: String::from("creation,checksum"),
+        checksum_algorithms: String::from("md5,sha1,whirlpool"),
+        ..TestHandler::default()
+    });
+
+    let result =
+        unwrap_future(client.get_server_info("/something")).expect("'get_server_info' call failed");
+
+    assert_eq!(
+        vec![ChecksumAlgorithm::Md5, ChecksumAlgorithm::Sha1],
+        result.checksum_algorithms
+    );
+}
+
+#[test]
+fn should_upload_file_with_checksum() {
+    let temp_file = create_temp_file();
+
+    let patch_requests = Arc::new(Mutex::new(Vec::new()));
+
+    let client = tus_client::Client::new(TestHandler {
+        upload_progress: 0,
+        total_upload_size: temp_file.len() as u64,
+        status_code: 204,
+        extensions: String::from("checksum"),
+        patch_requests: patch_requests.clone(),
+        checksum_algorithms: String::from("md5,sha1"),
+        ..TestHandler::default()
+    });
+
+    unwrap_future(client.upload("/something", temp_file)).expect("'upload' call failed");
+
+    let patch_requests = patch_requests.lock().unwrap();
+    assert!(!patch_requests.is_empty());
+    for headers in patch_requests.iter() {
+        assert!(headers.get("upload-checksum").unwrap().starts_with("sha1 "));
+    }
+}
+
+#[test]
+fn should_not_send_checksum_when_unsupported() {
+    let temp_file = create_temp_file();
+    let patch_requests = Arc::new(Mutex::new(Vec::new()));
+
+    let client = tus_client::Client::new(TestHandler {
+        upload_progress: 0,
+        total_upload_size: temp_file.len() as u64,
+        status_code: 204,
+        checksum_algorithms: String::from("sha1"),
+        patch_requests: patch_requests.clone(),
+        ..TestHandler::default()
+    });
+
+    unwrap_future(client.upload("/something", temp_file)).expect("'upload' call failed");
+
+    for headers in patch_requests.lock().unwrap().iter() {
+        assert!(!headers.contains_key("upload-checksum"));
+    }
+}
+
+#[test]
+fn should_resend_chunk_after_checksum_mismatch() {
+    let temp_file = create_temp_file();
+
+    let patch_requests = Arc::new(Mutex::new(Vec::new()));
+
+    let client = tus_client::Client::new(TestHandler {
+        upload_progress: 0,
+        total_upload_size: temp_file.len() as u64,
+        status_code: 204,
+        extensions: String::from("checksum"),
+        patch_requests: patch_requests.clone(),
+        checksum_algorithms: String::from("crc32"),
+        checksum_mismatches: AtomicUsize::new(2),
+        ..TestHandler::default()
+    });
+
+    unwrap_future(client.upload("/something", temp_file)).expect("'upload' call failed");
+
+    let patch_requests = patch_requests.lock().unwrap();
+    assert_eq!(3, patch_requests.len());
+    assert_eq!(patch_requests[0], patch_requests[2]);
+}
+
+#[test]
+fn should_fail_after_repeated_checksum_mismatches() {
+    let temp_file = create_temp_file();
+
+    let patch_requests = Arc::new(Mutex::new(Vec::new()));
+
+    let client = tus_client::Client::new(TestHandler {
+        upload_progress: 0,
+        total_upload_size: temp_file.len() as u64,
+        status_code: 204,
+        extensions: String::from("checksum"),
+        patch_requests: patch_requests.clone(),
+        checksum_algorithms: String::from("sha256"),
+        checksum_mismatches: AtomicUsize::new(10),
+        ..TestHandler::default()
+    });
+
+    let result = unwrap_future(client.upload("/something", temp_file));
+
+    match result {
+        Err(Error::ChecksumMismatch) => {}
+        _ => panic!("Expected 'Error::ChecksumMismatch'"),
+    }
+}
+
+#[test]
+fn should_calculate_checksum_header_value() {
+    assert_eq!(
+        "sha1 Kq5sNclPz7QV2+lfQIuc6R7oRu0=",
+        ChecksumAlgorithm::Sha1.header_value(b"hello world")
+    );
+    assert_eq!(
+        "md5 XrY7u+Ae7tCTyyK7j1rNww==",
+        ChecksumAlgorithm::Md5.header_value(b"hello world")
+    );
+    assert_eq!(
+        "crc32 DUoRhQ==",
+        ChecksumAlgorithm::Crc32.header_value(b"hello world")
+    );
+}
+
+#[test]
+fn should_create_upload_with_deferred_length() {
+    let post_requests = Arc::new(Mutex::new(Vec::new()));
+
+    let client = tus_client::Client::new(TestHandler {
+        status_code: 201,
+        post_requests: post_requests.clone(),
+        ..TestHandler::default()
+    });
+
+    let result = unwrap_future(client.create_with_deferred_length("/something", Metadata::new()))
+        .expect("'create_with_deferred_length' call failed");
+
+    assert!(!result.is_empty());
+    let post_requests = post_requests.lock().unwrap();
+    assert_eq!("1", post_requests[0]["upload-defer-length"]);
+    assert!(!post_requests[0].contains_key("upload-length"));
+}
+
+#[test]
+fn should_upload_stream_and_declare_length_on_last_chunk() {
+    let buffer = create_temp_file();
+    let patch_requests = Arc::new(Mutex::new(Vec::new()));
+
+    let client = tus_client::Client::new(TestHandler {
+        upload_progress: 0,
+        status_code: 204,
+        patch_requests: patch_requests.clone(),
+        ..TestHandler::default()
+    });
+
+    unwrap_future(client.upload_stream_with_chunk_size("/something", &buffer[..], 100 * 1024))
+        .expect("'upload_stream_with_chunk_size' call failed");
+
+    let patch_requests = patch_requests.lock().unwrap();
+    assert_eq!(8, patch_requests.len());
+    assert!(patch_requests[..7]
+        .iter()
+        .all(|headers| !headers.contains_key("upload-length")));
+    assert_eq!(buffer.len().to_string(), patch_requests[7]["upload-length"]);
+}
+
+#[test]
+fn should_declare_length_with_empty_chunk_at_chunk_boundary() {
+    let buffer: Vec<u8> = vec![7; 2048];
+    let patch_requests = Arc::new(Mutex::new(Vec::new()));
+
+    let client = tus_client::Client::new(TestHandler {
+        upload_progress: 0,
+        status_code: 204,
+        patch_requests: patch_requests.clone(),
+        ..TestHandler::default()
+    });
+
+    unwrap_future(client.upload_stream_with_chunk_size("/something", &buffer[..], 1024))
+        .expect("'upload_stream_with_chunk_size' call failed");
+
+    let patch_requests = patch_requests.lock().unwrap();
+    assert_eq!(3, patch_requests.len());
+    assert_eq!("2048", patch_requests[2]["upload-offset"]);
+    assert_eq!("2048", patch_requests[2]["upload-length"]);
+}
+
+#[test]
+fn should_upload_stream_with_chunk_size_of_zero() {
+    let patch_requests = Arc::new(Mutex::new(Vec::new()));
+
+    let client = tus_client::Client::new(TestHandler {
+        upload_progress: 0,
+        status_code: 204,
+        patch_requests: patch_requests.clone(),
+        ..TestHandler::default()
+    });
+
+    unwrap_future(client.upload_stream_with_chunk_size("/something", &b"hello"[..], 0))
+        .expect("'upload_stream_with_chunk_size' call failed");
+
+    let patch_requests = patch_requests.lock().unwrap();
+    assert_eq!(6, patch_requests.len());
+    assert_eq!("5", patch_requests[5]["upload-length"]);
+}
+
+#[test]
+fn should_resume_stream_upload_by_skipping_uploaded_bytes() {
+    let buffer: Vec<u8> = vec![7; 5000];
+    let patch_requests = Arc::new(Mutex::new(Vec::new()));
+
+    let client = tus_client::Client::new(TestHandler {
+        upload_progress: 1234,
+        total_upload_size: 5000,
+        status_code: 204,
+        patch_requests: patch_requests.clone(),
+        ..TestHandler::default()
+    });
+
+    unwrap_future(client.upload_stream("/something", &buffer[..]))
+        .expect("'upload_stream' call failed");
+
+    let patch_requests = patch_requests.lock().unwrap();
+    assert_eq!(1, patch_requests.len());
+    assert_eq!("1234", patch_requests[0]["upload-offset"]);
+    assert_eq!("5000", patch_requests[0]["upload-length"]);
+}
+
+#[test]
+fn should_upload_file_in_parallel() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let client = tus_client::Client::new(server.clone());
+
+    let mut metadata = Metadata::new();
+    metadata.insert("filename", "image.tif").unwrap();
+
+    let url = unwrap_future(client.upload_parallel("/files", &buffer, 4, metadata))
+        .expect("'upload_parallel' call failed")
+        .url;
+
+    let info = unwrap_future(client.get_info(&url)).expect("'get_info' call failed");
+    assert_eq!(buffer.len() as u64, info.total_size.unwrap());
+    assert_eq!(buffer.len() as u64, info.bytes_uploaded);
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+    assert_eq!(5, server.upload_urls().len());
+}
+
+#[test]
+fn should_resume_interrupted_partial_upload() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    fail_patches(
+        &server,
+        2,
+        FaultKind::HandlerError("connection reset".to_owned()),
+    );
+    let delays = Arc::new(Mutex::new(Vec::new()));
+    let client = ClientBuilder::new()
+        .retry_policy(RetryPolicy::new().max_attempts(2).jitter(0.0))
+        .timer(RecordingTimer {
+            delays: delays.clone(),
+        })
+        .build(server.clone());
+
+    let url = unwrap_future(client.upload_parallel("/files", &buffer, 3, Metadata::new()))
+        .expect("'upload_parallel' call failed")
+        .url;
+
+    let info = unwrap_future(client.get_info(&url)).expect("'get_info' call failed");
+    assert_eq!(buffer.len() as u64, info.bytes_uploaded);
+    assert_eq!(2, delays.lock().unwrap().len());
+}
+
+#[test]
+fn should_not_retry_partial_upload_which_is_forbidden() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    server.inject_fault(Fault::on(HttpMethod::Head, FaultKind::Status(403)));
+    let delays = Arc::new(Mutex::new(Vec::new()));
+    let client = ClientBuilder::new()
+        .retry_policy(RetryPolicy::new())
+        .timer(RecordingTimer {
+            delays: delays.clone(),
+        })
+        .build(server.clone());
+
+    let result = unwrap_future(client.upload_parallel("/files", &buffer, 3, Metadata::new()));
+
+    assert!(result.is_err());
+    assert!(delays.lock().unwrap().is_empty());
+    assert_eq!(
+        1,
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.method == HttpMethod::Head)
+            .count()
+    );
+}
+
+#[test]
+fn should_report_progress_of_whole_file_for_parallel_upload() {
+    let buffer = create_temp_file();
+    let (sender, events) = progress_channel();
+    let server = InMemoryServer::new();
+    let client = ClientBuilder::new()
+        .chunk_size(100 * 1024)
+        .progress_listener(sender)
+        .build(server.clone());
+
+    unwrap_future(client.upload_parallel("/files", &buffer, 3, Metadata::new()))
+        .expect("'upload_parallel' call failed");
+    drop(client);
+
+    let events: Vec<ProgressEvent> = futures::executor::block_on(events.collect());
+    let mut uploaded = 0;
+    for event in &events[..events.len() - 1] {
+        match event {
+            ProgressEvent::ChunkStarted { offset, len } => {
+                assert!(offset + *len as u64 <= buffer.len() as u64)
+            }
+            ProgressEvent::Progress {
+                bytes_uploaded,
+                total_size,
+                ..
+            } => {
+                assert_eq!(Some(buffer.len() as u64), *total_size);
+                assert!(*bytes_uploaded >= uploaded);
+                uploaded = *bytes_uploaded;
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+    }
+    assert_eq!(buffer.len() as u64, uploaded);
+    assert_eq!(
+        Some(&ProgressEvent::Completed {
+            bytes_uploaded: buffer.len() as u64
+        }),
+        events.last()
+    );
+}
+
+#[test]
+fn should_not_upload_in_parallel_without_concatenation() {
+    let server = InMemoryServer::with_extensions(vec![TusExtension::Creation]);
+    let client = tus_client::Client::new(server.clone());
+
+    match unwrap_future(client.upload_parallel("/files", b"hello".to_vec(), 2, Metadata::new())) {
+        Err(Error::ConcatenationUnsupported) => {}
+        result => panic!(
+            "Expected 'Error::ConcatenationUnsupported', got {:?}",
+            result
+        ),
+    }
+    assert!(server.upload_urls().is_empty());
+}
+
+#[test]
+fn should_create_and_upload_small_file_in_single_request() {
+    let buffer: Vec<u8> = (0..4096).map(|_| rand::random::<u8>()).collect();
+    let server = InMemoryServer::with_extensions(vec![
+        TusExtension::Creation,
+        TusExtension::CreationWithUpload,
+    ]);
+    let client = tus_client::Client::new(server.clone());
+
+    let url = unwrap_future(client.create_and_upload("/files", &buffer, Metadata::new()))
+        .expect("'create_and_upload' call failed")
+        .url;
+
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+    let requests = server.requests();
+    assert_eq!(2, requests.len());
+    assert_eq!(HttpMethod::Options, requests[0].method);
+    assert_eq!(HttpMethod::Post, requests[1].method);
+    assert_eq!(
+        "application/offset+octet-stream",
+        requests[1].headers["content-type"]
+    );
+}
+
+#[test]
+fn should_create_and_upload_remainder_after_first_chunk() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::with_extensions(vec![
+        TusExtension::Creation,
+        TusExtension::CreationWithUpload,
+    ]);
+    let client = tus_client::Client::new(server.clone());
+
+    for _ in 0..2 {
+        let url = unwrap_future(client.create_and_upload_with_chunk_size(
+            "/files",
+            &buffer,
+            Metadata::new(),
+            512 * 1024,
+        ))
+        .expect("'create_and_upload_with_chunk_size' call failed")
+        .url;
+
+        assert_eq!(buffer, server.upload(&url).unwrap().data);
+    }
+
+    let methods: Vec<HttpMethod> = server.requests().iter().map(|r| r.method).collect();
+    assert_eq!(
+        vec![
+            HttpMethod::Options,
+            HttpMethod::Post,
+            HttpMethod::Patch,
+            HttpMethod::Post,
+            HttpMethod::Patch
+        ],
+        methods
+    );
+}
+
+#[test]
+fn should_create_and_upload_without_creation_with_upload() {
+    let buffer: Vec<u8> = (0..4096).map(|_| rand::random::<u8>()).collect();
+    let server = InMemoryServer::with_extensions(vec![TusExtension::Creation]);
+    let client = tus_client::Client::new(server.clone());
+
+    let url = unwrap_future(client.create_and_upload("/files", &buffer, Metadata::new()))
+        .expect("'create_and_upload' call failed")
+        .url;
+
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+    let requests = server.requests();
+    assert_eq!(3, requests.len());
+    assert!(!requests[1].headers.contains_key("content-type"));
+}
+
+#[test]
+fn should_create_and_upload_when_server_info_fails() {
+    let buffer: Vec<u8> = (0..4096).map(|_| rand::random::<u8>()).collect();
+    let server = InMemoryServer::new();
+    server.inject_fault(Fault::on(HttpMethod::Options, FaultKind::Status(500)));
+    let client = tus_client::Client::new(server.clone());
+
+    let url = unwrap_future(client.create_and_upload("/files", &buffer, Metadata::new()))
+        .expect("'create_and_upload' call failed")
+        .url;
+
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+    let requests = server.requests();
+    assert_eq!(HttpMethod::Post, requests[1].method);
+    assert!(!requests[1].headers.contains_key("content-type"));
+    assert_eq!(HttpMethod::Options, requests[2].method);
+    assert_eq!(HttpMethod::Patch, requests[3].method);
+    assert!(requests[3].headers.contains_key("upload-checksum"));
+}
+
+#[test]
+fn should_not_create_upload_when_server_info_request_fails_to_send() {
+    let buffer: Vec<u8> = (0..4096).map(|_| rand::random::<u8>()).collect();
+    let server = InMemoryServer::new();
+    server.inject_fault(Fault::on(
+        HttpMethod::Options,
+        FaultKind::HandlerError("connection refused".to_owned()),
+    ));
+    let client = tus_client::Client::new(server.clone());
+
+    match unwrap_future(client.create_and_upload("/files", &buffer, Metadata::new())) {
+        Err(Error::HttpHandlerError(_)) => {}
+        result => panic!("Expected 'Error::HttpHandlerError', got {:?}", result),
+    }
+    assert_eq!(1, server.requests().len());
+}
+
+struct ImmediateTimer;
+
+impl Timer for ImmediateTimer {
+    fn sleep(&self, _duration: Duration) -> futures::future::BoxFuture<'static, ()> {
+        Box::pin(futures::future::ready(()))
+    }
+}
+
+/// Records the delays it is asked to wait for, without waiting.
+struct RecordingTimer {
+    delays: Arc<Mutex<Vec<Duration>>>,
+}
+
+impl Timer for RecordingTimer {
+    fn sleep(&self, duration: Duration) -> futures::future::BoxFuture<'static, ()> {
+        self.delays.lock().unwrap().push(duration);
+        Box::pin(futures::future::ready(()))
+    }
+}
+
+fn create_upload(server: &InMemoryServer, len: usize) -> String {
+    let client = tus_client::Client::new(server.clone());
+    unwrap_future(client.create("/files", len as u64)).expect("'create' call failed")
+}
+
+fn fail_patches(server: &InMemoryServer, count: usize, kind: FaultKind) {
+    for _ in 0..count {
+        server.inject_fault(Fault::on(HttpMethod::Patch, kind.clone()));
+    }
+}
+
+#[test]
+fn should_retry_failed_chunk_after_resyncing_offset() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    fail_patches(
+        &server,
+        2,
+        FaultKind::HandlerError("connection reset".to_owned()),
+    );
+    let url = create_upload(&server, buffer.len());
+
+    let client = ClientBuilder::new()
+        .retry_policy(RetryPolicy::new().max_attempts(3))
+        .timer(ImmediateTimer)
+        .build(server.clone());
+
+    unwrap_future(client.upload_with_chunk_size(&url, &buffer, 256 * 1024))
+        .expect("'upload_with_chunk_size' call failed");
+
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+    let heads = server
+        .requests()
+        .iter()
+        .filter(|r| r.method == HttpMethod::Head)
+        .count();
+    assert_eq!(3, heads);
+}
+
+#[test]
+fn should_retry_retryable_status_codes() {
+    let buffer: Vec<u8> = (0..4096).map(|_| rand::random::<u8>()).collect();
+    let server = InMemoryServer::new();
+    fail_patches(&server, 1, FaultKind::Status(503));
+    let url = create_upload(&server, buffer.len());
+
+    let client = ClientBuilder::new()
+        .retry_policy(RetryPolicy::new())
+        .timer(ImmediateTimer)
+        .build(server.clone());
+
+    unwrap_future(client.upload(&url, &buffer)).expect("'upload' call failed");
+
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+}
+
+#[test]
+fn should_not_retry_other_status_codes() {
+    let buffer: Vec<u8> = (0..4096).map(|_| rand::random::<u8>()).collect();
+    let server = InMemoryServer::new();
+    fail_patches(&server, 1, FaultKind::Status(400));
+    let url = create_upload(&server, buffer.len());
+
+    let client = ClientBuilder::new()
+        .retry_policy(RetryPolicy::new())
+        .timer(ImmediateTimer)
+        .build(server.clone());
+
+    match unwrap_future(client.upload(&url, &buffer)) {
+        Err(Error::UnexpectedStatusCode(400)) => {}
+        _ => panic!("Expected 'Error::UnexpectedStatusCode(400)'"),
+    }
+}
+
+#[test]
+fn should_give_up_after_max_attempts() {
+    let buffer: Vec<u8> = (0..4096).map(|_| rand::random::<u8>()).collect();
+    let server = InMemoryServer::new();
+    fail_patches(
+        &server,
+        3,
+        FaultKind::HandlerError("connection reset".to_owned()),
+    );
+    let url = create_upload(&server, buffer.len());
+
+    let client = ClientBuilder::new()
+        .retry_policy(RetryPolicy::new().max_attempts(3))
+        .timer(ImmediateTimer)
+        .build(server.clone());
+
+    match unwrap_future(client.upload(&url, &buffer)) {
+        Err(Error::HttpHandlerError(_)) => {}
+        _ => panic!("Expected 'Error::HttpHandlerError'"),
+    }
+}
+
+#[test]
+fn should_not_retry_by_default() {
+    let buffer: Vec<u8> = (0..4096).map(|_| rand::random::<u8>()).collect();
+    let server = InMemoryServer::new();
+    fail_patches(
+        &server,
+        1,
+        FaultKind::HandlerError("connection reset".to_owned()),
+    );
+    let url = create_upload(&server, buffer.len());
+
+    let client = tus_client::Client::new(server.clone());
+
+    let err = unwrap_future(client.upload(&url, &buffer));
+    eprintln!("{:?} {:?}", err, server.requests());
+}
+
+#[test]
+fn should_retry_stream_upload_from_buffer() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    fail_patches(
+        &server,
+        2,
+        FaultKind::HandlerError("connection reset".to_owned()),
+    );
+    let url = create_upload(&server, buffer.len());
+
+    let client = ClientBuilder::new()
+        .retry_policy(RetryPolicy::new())
+        .timer(ImmediateTimer)
+        .build(server.clone());
+
+    unwrap_future(client.upload_stream_with_chunk_size(&url, &buffer[..], 256 * 1024))
+        .expect("'upload_stream_with_chunk_size' call failed");
+
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+}
+
+#[test]
+fn should_double_retry_delay_up_to_max_delay() {
+    let policy = RetryPolicy::new()
+        .base_delay(Duration::from_millis(100))
+        .max_delay(Duration::from_millis(300))
+        .jitter(0.0);
+
+    assert_eq!(Duration::from_millis(100), policy.delay(1));
+    assert_eq!(Duration::from_millis(200), policy.delay(2));
+    assert_eq!(Duration::from_millis(300), policy.delay(3));
+    assert_eq!(Duration::from_millis(300), policy.delay(100));
+}
+
+#[test]
+fn should_randomize_retry_delay_within_jitter() {
+    let policy = RetryPolicy::new()
+        .base_delay(Duration::from_millis(1000))
+        .jitter(0.25);
+
+    for _ in 0..100 {
+        let delay = policy.delay(1);
+        assert!(delay > Duration::from_millis(750) && delay <= Duration::from_millis(1000));
+    }
+}
+
+#[test]
+fn should_sleep_on_thread_timer() {
+    let start = std::time::Instant::now();
+
+    futures::executor::block_on(ThreadTimer.sleep(Duration::from_millis(20)));
+
+    assert!(start.elapsed() >= Duration::from_millis(20));
+}
+
+#[test]
+fn should_wake_earlier_thread_timer_sleeps_first() {
+    let waker = futures::task::noop_waker();
+    let mut context = std::task::Context::from_waker(&waker);
+    let mut long_sleep = ThreadTimer.sleep(Duration::from_secs(3600));
+    let mut dropped_sleep = ThreadTimer.sleep(Duration::from_secs(1800));
+    assert!(long_sleep.as_mut().poll(&mut context).is_pending());
+    assert!(dropped_sleep.as_mut().poll(&mut context).is_pending());
+    drop(dropped_sleep);
+    let start = std::time::Instant::now();
+
+    futures::executor::block_on(ThreadTimer.sleep(Duration::from_millis(20)));
+
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert!(start.elapsed() < Duration::from_secs(60));
+    assert!(long_sleep.as_mut().poll(&mut context).is_pending());
+}
+
+#[test]
+fn should_report_upload_progress_to_listener() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    let client = tus_client::Client::new(server.clone());
+
+    let events = Mutex::new(Vec::new());
+    let listener = |event: &ProgressEvent| events.lock().unwrap().push(event.clone());
+
+    unwrap_future(client.upload_with_progress(&url, &buffer, 512 * 1024, &listener))
+        .expect("'upload_with_progress' call failed");
+
+    let events = events.into_inner().unwrap();
+    assert_eq!(5, events.len());
+    assert_eq!(
+        ProgressEvent::ChunkStarted {
+            offset: 0,
+            len: 512 * 1024
+        },
+        events[0]
+    );
+    match &events[1] {
+        ProgressEvent::Progress {
+            bytes_uploaded,
+            total_size,
+            ..
+        } => {
+            assert_eq!(512 * 1024, *bytes_uploaded);
+            assert_eq!(Some(buffer.len() as u64), *total_size);
+        }
+        event => panic!("Expected 'ProgressEvent::Progress', got {:?}", event),
+    }
+    assert_eq!(
+        ProgressEvent::ChunkStarted {
+            offset: 512 * 1024,
+            len: buffer.len() - 512 * 1024
+        },
+        events[2]
+    );
+    assert_eq!(
+        ProgressEvent::Completed {
+            bytes_uploaded: buffer.len() as u64
+        },
+        events[4]
+    );
+}
+
+#[test]
+fn should_report_retries_to_listener() {
+    let buffer: Vec<u8> = (0..4096).map(|_| rand::random::<u8>()).collect();
+    let server = InMemoryServer::new();
+    fail_patches(
+        &server,
+        1,
+        FaultKind::HandlerError("connection reset".to_owned()),
+    );
+    let url = create_upload(&server, buffer.len());
+
+    let client = ClientBuilder::new()
+        .retry_policy(RetryPolicy::new())
+        .timer(ImmediateTimer)
+        .build(server.clone());
+
+    let events = Mutex::new(Vec::new());
+    let listener = |event: &ProgressEvent| events.lock().unwrap().push(event.clone());
+
+    unwrap_future(client.upload_with_progress(&url, &buffer, 1024, &listener))
+        .expect("'upload_with_progress' call failed");
+
+    let events = events.into_inner().unwrap();
+    let retries: Vec<&ProgressEvent> = events
+        .iter()
+        .filter(|event| matches!(event, ProgressEvent::Retrying { .. }))
+        .collect();
+    assert_eq!(1, retries.len());
+    match retries[0] {
+        ProgressEvent::Retrying { attempt, .. } => assert_eq!(1, *attempt),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn should_report_upload_progress_to_stream() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    let client = tus_client::Client::new(server.clone());
+
+    let (sender, stream) = progress_channel();
+
+    unwrap_future(client.upload_with_progress(&url, &buffer, 256 * 1024, &sender))
+        .expect("'upload_with_progress' call failed");
+    drop(sender);
+
+    let events: Vec<ProgressEvent> = futures::executor::block_on(stream.collect());
+    assert_eq!(7, events.len());
+    assert_eq!(
+        Some(&ProgressEvent::Completed {
+            bytes_uploaded: buffer.len() as u64
+        }),
+        events.last()
+    );
+}
+
+fn create_temp_store() -> (JsonFileStore, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("tus_store_{}.json", rand::random::<u64>()));
+    (JsonFileStore::new(&path), path)
+}
+
+fn create_fingerprint(len: usize) -> Fingerprint {
+    Fingerprint {
+        path: "/path/to/file".into(),
+        size: len as u64,
+        modified: Some(std::time::UNIX_EPOCH + Duration::from_secs(1_600_000_000)),
+        content_hash: None,
+    }
+}
+
+#[test]
+fn should_persist_uploads_in_json_file_store() {
+    let (store, path) = create_temp_store();
+    let fingerprint = create_fingerprint(1234);
+    let other_fingerprint = fingerprint.clone().with_content_hash("abc".to_owned());
+
+    let mut metadata = Metadata::new();
+    metadata.insert("filename", "image.tif").unwrap();
+    let upload = StoredUpload {
+        url: "/files/0".to_owned(),
+        metadata,
+    };
+
+    store.set(&fingerprint, upload.clone()).unwrap();
+
+    let reopened = JsonFileStore::new(&path);
+    assert_eq!(Some(upload), reopened.get(&fingerprint).unwrap());
+    assert_eq!(None, reopened.get(&other_fingerprint).unwrap());
+
+    reopened.remove(&fingerprint).unwrap();
+    assert_eq!(None, store.get(&fingerprint).unwrap());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn should_fingerprint_file_from_path() {
+    let path = std::env::temp_dir().join(format!("tus_file_{}", rand::random::<u64>()));
+    std::fs::write(&path, [7; 1234]).unwrap();
+
+    let fingerprint = Fingerprint::from_path(&path).unwrap();
+
+    assert_eq!(1234, fingerprint.size);
+    assert!(fingerprint.modified.is_some());
+    assert_eq!(fingerprint, Fingerprint::from_path(&path).unwrap());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn should_resume_stored_upload() {
+    let (store, path) = create_temp_store();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 4096);
+    let client = tus_client::Client::new(server.clone());
+    let fingerprint = create_fingerprint(4096);
+    store
+        .set(
+            &fingerprint,
+            StoredUpload {
+                url: url.clone(),
+                metadata: Metadata::new(),
+            },
+        )
+        .unwrap();
+
+    let result =
+        unwrap_future(client.create_or_resume("/files", &store, &fingerprint, Metadata::new()))
+            .expect("'create_or_resume' call failed");
+
+    assert_eq!(url, result);
+    // only the POST of `create_upload`, the stored upload is resumed
+    assert_eq!(
+        1,
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.method == HttpMethod::Post)
+            .count()
+    );
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn should_create_new_upload_when_stored_upload_is_gone() {
+    let (store, path) = create_temp_store();
+    let client = tus_client::Client::new(InMemoryServer::new());
+    let fingerprint = create_fingerprint(4096);
+    store
+        .set(
+            &fingerprint,
+            StoredUpload {
+                url: "/files/gone".to_owned(),
+                metadata: Metadata::new(),
+            },
+        )
+        .unwrap();
+
+    let result =
+        unwrap_future(client.create_or_resume("/files", &store, &fingerprint, Metadata::new()))
+            .expect("'create_or_resume' call failed");
+
+    assert_ne!("/files/gone", result);
+    assert_eq!(result, store.get(&fingerprint).unwrap().unwrap().url);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn should_resume_interrupted_upload_with_store() {
+    let buffer = create_temp_file();
+    let (store, path) = create_temp_store();
+    let fingerprint = create_fingerprint(buffer.len());
+    let server = InMemoryServer::new();
+    fail_patches(
+        &server,
+        1,
+        FaultKind::HandlerError("connection reset".to_owned()),
+    );
+    let client = tus_client::Client::new(server.clone());
+
+    let result = unwrap_future(client.upload_with_store(
+        "/files",
+        &buffer,
+        &store,
+        &fingerprint,
+        Metadata::new(),
+    ));
+    assert!(result.is_err());
+    let stored_url = store.get(&fingerprint).unwrap().unwrap().url;
+
+    let url = unwrap_future(client.upload_with_store(
+        "/files",
+        &buffer,
+        &store,
+        &fingerprint,
+        Metadata::new(),
+    ))
+    .expect("'upload_with_store' call failed")
+    .url;
+
+    assert_eq!(stored_url, url);
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+    assert_eq!(None, store.get(&fingerprint).unwrap());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn should_report_upload_expiry() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new().expires_after(Duration::from_secs(3600));
+    let url = create_upload(&server, buffer.len());
+    let client = tus_client::Client::new(server.clone());
+
+    let info = unwrap_future(client.get_info(&url)).expect("'get_info' call failed");
+    let expires = info.expires.expect("expected an expiry time");
+    assert!(expires > SystemTime::now());
+
+    let result = unwrap_future(client.upload_with_chunk_size(&url, &buffer, 256 * 1024))
+        .expect("'upload_with_chunk_size' call failed");
+
+    // the upload is complete, so it no longer expires
+    assert_eq!(None, result.expires);
+    assert_eq!(url, result.url);
+    assert_eq!(buffer.len() as u64, result.bytes_uploaded);
+}
+
+#[test]
+fn should_fail_with_gone_for_expired_upload() {
+    let server = InMemoryServer::new().expires_after(Duration::from_secs(3600));
+    let url = create_upload(&server, 4096);
+    let client = tus_client::Client::new(server.clone());
+    server.expire(&url);
+
+    match unwrap_future(client.upload(&url, vec![0; 4096])) {
+        Err(Error::Gone) => {}
+        result => panic!("Expected 'Error::Gone', got {:?}", result),
+    }
+}
+
+#[test]
+fn should_not_recreate_expired_upload_by_default() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    fail_patches(&server, 1, FaultKind::Status(410));
+    let client = tus_client::Client::new(server.clone());
+
+    let result = unwrap_future(client.create_and_upload_with_chunk_size(
+        "/files",
+        &buffer,
+        Metadata::new(),
+        256 * 1024,
+    ));
+
+    match result {
+        Err(Error::Gone) => {}
+        result => panic!("Expected 'Error::Gone', got {:?}", result),
+    }
+}
+
+#[test]
+fn should_recreate_expired_upload_with_same_metadata() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    fail_patches(&server, 1, FaultKind::Status(410));
+    let client = ClientBuilder::new()
+        .recreate_expired_uploads(true)
+        .build(server.clone());
+
+    let mut metadata = Metadata::new();
+    metadata.insert("filename", "image.tif").unwrap();
+
+    let result = unwrap_future(client.create_and_upload_with_chunk_size(
+        "/files",
+        &buffer,
+        metadata.clone(),
+        256 * 1024,
+    ))
+    .expect("'create_and_upload_with_chunk_size' call failed");
+
+    assert_eq!(2, server.upload_urls().len());
+    assert_eq!(buffer, server.upload(&result.url).unwrap().data);
+    let info = unwrap_future(client.get_info(&result.url)).expect("'get_info' call failed");
+    assert_eq!(Some(metadata), info.metadata);
+}
+
+#[test]
+fn should_replace_expired_stored_upload() {
+    let (store, path) = create_temp_store();
+    let server = InMemoryServer::new();
+    let expired_url = create_upload(&server, 4096);
+    server.expire(&expired_url);
+    let client = tus_client::Client::new(server.clone());
+    let fingerprint = create_fingerprint(4096);
+    store
+        .set(
+            &fingerprint,
+            StoredUpload {
+                url: expired_url.clone(),
+                metadata: Metadata::new(),
+            },
+        )
+        .unwrap();
+
+    let result =
+        unwrap_future(client.create_or_resume("/files", &store, &fingerprint, Metadata::new()))
+            .expect("'create_or_resume' call failed");
+
+    assert_ne!(expired_url, result);
+    assert_eq!(result, store.get(&fingerprint).unwrap().unwrap().url);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+/// Controls an upload through its handle once a chunk of it was sent.
+struct AfterChunk {
+    server: InMemoryServer,
+    handle: Arc<Mutex<Option<UploadHandle>>>,
+    action: fn(&UploadHandle),
+}
+
+impl AfterChunk {
+    fn new(server: &InMemoryServer, action: fn(&UploadHandle)) -> Self {
+        AfterChunk {
+            server: server.clone(),
+            handle: Arc::new(Mutex::new(None)),
+            action,
+        }
+    }
+}
+
+impl HttpHandler for AfterChunk {
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        let is_patch = req.method == HttpMethod::Patch;
+        let response = self.server.handle_request(req).await;
+        if is_patch {
+            if let Some(handle) = self.handle.lock().unwrap().as_ref() {
+                (self.action)(handle);
+            }
+        }
+        response
+    }
+}
+
+/// Polls a future once, without waiting for it to be woken.
+fn poll_once<F>(fut: &mut Pin<Box<F>>) -> Poll<F::Output>
+where
+    F: Future,
+{
+    let waker = futures::task::noop_waker();
+    let mut context = std::task::Context::from_waker(&waker);
+    fut.as_mut().poll(&mut context)
+}
+
+#[test]
+fn should_stop_cancelled_upload_after_in_flight_chunk() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    let handler = AfterChunk::new(&server, UploadHandle::cancel);
+    let handle = handler.handle.clone();
+    let client = tus_client::Client::new(handler);
+
+    let mut upload = client.begin_upload(&url, &buffer).chunk_size(256 * 1024);
+    *handle.lock().unwrap() = Some(upload.handle());
+
+    match unwrap_future(upload.run()) {
+        Err(Error::Cancelled) => {}
+        result => panic!("Expected 'Error::Cancelled', got {:?}", result),
+    }
+    assert!(handle.lock().unwrap().as_ref().unwrap().is_cancelled());
+    assert_eq!(UploadState::Cancelled, upload.state());
+    assert_eq!(256 * 1024, upload.offset());
+    assert_eq!(256 * 1024, server.upload(&url).unwrap().data.len());
+}
+
+#[test]
+fn should_terminate_cancelled_upload() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    let handler = AfterChunk::new(&server, UploadHandle::cancel);
+    let handle = handler.handle.clone();
+    let client = tus_client::Client::new(handler);
+
+    let mut upload = client
+        .begin_upload(&url, &buffer)
+        .chunk_size(256 * 1024)
+        .terminate_on_cancel(true);
+    *handle.lock().unwrap() = Some(upload.handle());
+
+    assert!(matches!(unwrap_future(upload.run()), Err(Error::Cancelled)));
+    assert!(server.upload(&url).is_none());
+    assert_eq!(HttpMethod::Delete, server.requests().last().unwrap().method);
+}
+
+#[test]
+fn should_complete_upload_which_is_not_cancelled() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    let client = tus_client::Client::new(server.clone());
+
+    let mut upload = client.begin_upload(&url, &buffer);
+    let handle = upload.handle();
+
+    unwrap_future(upload.run()).expect("'run' call failed");
+
+    assert!(!handle.is_cancelled());
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+}
+
+#[test]
+fn should_pause_and_resume_upload() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    let handler = AfterChunk::new(&server, UploadHandle::pause);
+    let handle = handler.handle.clone();
+    let client = tus_client::Client::new(handler);
+
+    let mut upload = client.begin_upload(&url, &buffer).chunk_size(256 * 1024);
+    let upload_handle = upload.handle();
+    *handle.lock().unwrap() = Some(upload.handle());
+    assert_eq!(UploadState::Created, upload.state());
+
+    let mut run = Box::pin(upload.run());
+    assert!(poll_once(&mut run).is_pending());
+    assert_eq!(UploadState::Paused, upload_handle.state());
+    assert_eq!(256 * 1024, upload_handle.offset());
+    assert_eq!(256 * 1024, server.upload(&url).unwrap().data.len());
+
+    *handle.lock().unwrap() = None;
+    upload_handle.resume();
+    match poll_once(&mut run) {
+        Poll::Ready(result) => result.expect("'run' call failed"),
+        Poll::Pending => panic!("Resumed upload did not complete"),
+    };
+    drop(run);
+
+    assert_eq!(UploadState::Completed, upload.state());
+    assert_eq!(buffer.len() as u64, upload.offset());
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+}
+
+#[test]
+fn should_cancel_paused_upload() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    let handler = AfterChunk::new(&server, UploadHandle::pause);
+    let handle = handler.handle.clone();
+    let client = tus_client::Client::new(handler);
+
+    let mut upload = client.begin_upload(&url, &buffer).chunk_size(256 * 1024);
+    let upload_handle = upload.handle();
+    *handle.lock().unwrap() = Some(upload.handle());
+
+    let mut run = Box::pin(upload.run());
+    assert!(poll_once(&mut run).is_pending());
+    upload_handle.cancel();
+    assert!(matches!(
+        poll_once(&mut run),
+        Poll::Ready(Err(Error::Cancelled))
+    ));
+    drop(run);
+
+    assert_eq!(UploadState::Cancelled, upload.state());
+    assert_eq!(256 * 1024, server.upload(&url).unwrap().data.len());
+}
+
+#[test]
+fn should_resume_failed_upload_when_run_again() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    let client = tus_client::Client::new(server.clone());
+    let mut upload = client.begin_upload(&url, &buffer).chunk_size(256 * 1024);
+
+    server.inject_fault(Fault::on(HttpMethod::Patch, FaultKind::Status(500)));
+    assert!(unwrap_future(upload.run()).is_err());
+    assert_eq!(UploadState::Failed, upload.state());
+
+    unwrap_future(upload.run()).expect("'run' call failed");
+
+    assert_eq!(UploadState::Completed, upload.state());
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+}
+
+#[test]
+fn should_report_locked_upload() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 4096);
+    fail_patches(&server, 1, FaultKind::Status(423));
+    let client = tus_client::Client::new(server.clone());
+
+    match unwrap_future(client.upload(&url, vec![0; 4096])) {
+        Err(Error::Locked) => {}
+        result => panic!("Expected 'Error::Locked', got {:?}", result),
+    }
+}
+
+#[test]
+fn should_retry_locked_upload() {
+    let buffer = vec![1; 4096];
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    fail_patches(&server, 2, FaultKind::Status(423));
+    let client = ClientBuilder::new()
+        .retry_policy(RetryPolicy::new())
+        .timer(ImmediateTimer)
+        .build(server.clone());
+
+    unwrap_future(client.upload(&url, &buffer)).expect("'upload' call failed");
+
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+}
+
+#[test]
+fn should_report_deleting_unknown_upload_as_not_found() {
+    let client = tus_client::Client::new(InMemoryServer::new());
+
+    match unwrap_future(client.delete("/files/unknown")) {
+        Err(Error::NotFoundError) => {}
+        result => panic!("Expected 'Error::NotFoundError', got {:?}", result),
+    }
+}
+
+#[test]
+fn should_upload_with_chunk_size_of_builder() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    let client = ClientBuilder::new()
+        .chunk_size(256 * 1024)
+        .build(server.clone());
+
+    unwrap_future(client.upload(&url, &buffer)).expect("'upload' call failed");
+
+    let patches = server
+        .requests()
+        .iter()
+        .filter(|r| r.method == HttpMethod::Patch)
+        .count();
+    assert_eq!(3, patches);
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+}
+
+#[test]
+fn should_treat_chunk_size_of_zero_as_one() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 5);
+    let client = tus_client::Client::new(server.clone());
+
+    unwrap_future(client.upload_with_chunk_size(&url, b"hello".to_vec(), 0))
+        .expect("'upload_with_chunk_size' call failed");
+
+    let patches = server
+        .requests()
+        .iter()
+        .filter(|r| r.method == HttpMethod::Patch)
+        .count();
+    assert_eq!(5, patches);
+    assert_eq!(b"hello", &server.upload(&url).unwrap().data[..]);
+
+    let url = create_upload(&server, 5);
+    let mut upload = client.begin_upload(&url, b"hello".to_vec()).chunk_size(0);
+    unwrap_future(upload.run()).expect("'run' call failed");
+
+    assert_eq!(b"hello", &server.upload(&url).unwrap().data[..]);
+}
+
+#[test]
+fn should_send_default_headers_and_tus_version() {
+    let server = InMemoryServer::new();
+    let client = ClientBuilder::new()
+        .header("Authorization", "Bearer secret")
+        .header("Tus-Resumable", "0.2.2")
+        .tus_version("1.0.0")
+        .build(server.clone());
+
+    let url = unwrap_future(client.create("/files", 3)).expect("'create' call failed");
+    unwrap_future(client.upload(&url, b"abc".to_vec())).expect("'upload' call failed");
+
+    for request in server.requests() {
+        assert_eq!("Bearer secret", request.headers["Authorization"]);
+        let version = request.headers.get("tus-resumable").map(String::as_str);
+        if request.method == HttpMethod::Options {
+            assert_eq!(None, version);
+        } else {
+            assert_eq!(Some("1.0.0"), version);
+        }
+    }
+}
+
+#[test]
+fn should_only_override_patch_and_delete() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 3);
+    let client = ClientBuilder::new()
+        .method_override(MethodOverride::PatchAndDelete)
+        .build(server.clone());
+
+    unwrap_future(client.upload(&url, b"abc".to_vec())).expect("'upload' call failed");
+    unwrap_future(client.delete(&url)).expect("'delete' call failed");
+
+    let overridden: Vec<_> = server
+        .requests()
+        .iter()
+        .skip(1)
+        .map(|r| (r.method, r.headers.get("x-http-method-override").cloned()))
+        .collect();
+    assert_eq!(
+        vec![
+            (HttpMethod::Head, None),
+            (HttpMethod::Options, None),
+            (HttpMethod::Patch, Some("Patch".to_owned())),
+            (HttpMethod::Delete, Some("Delete".to_owned())),
+        ],
+        overridden
+    );
+    assert!(server.upload(&url).is_none());
+}
+
+/// Never responds to a request.
+struct UnresponsiveHandler;
+
+impl HttpHandler for UnresponsiveHandler {
+    async fn handle_request<'a>(&self, _req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        futures::future::pending().await
+    }
+}
+
+#[test]
+fn should_time_out_request() {
+    let client = ClientBuilder::new()
+        .request_timeout(Duration::from_secs(30))
+        .timer(ImmediateTimer)
+        .build(UnresponsiveHandler);
+
+    match unwrap_future(client.get_info("/files/1")) {
+        Err(Error::Timeout) => {}
+        result => panic!("Expected 'Error::Timeout', got {:?}", result),
+    }
+}
+
+#[test]
+fn should_use_checksum_preference_of_builder() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 3);
+    let client = ClientBuilder::new()
+        .checksum_preference(vec![ChecksumAlgorithm::Md5])
+        .build(server.clone());
+
+    unwrap_future(client.upload(&url, b"abc".to_vec())).expect("'upload' call failed");
+
+    let patch = server
+        .requests()
+        .into_iter()
+        .find(|r| r.method == HttpMethod::Patch)
+        .unwrap();
+    assert!(patch.headers["upload-checksum"].starts_with("md5 "));
+}
+
+#[test]
+fn should_upload_without_checksums_when_preference_is_empty() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 3);
+    let client = ClientBuilder::new()
+        .checksum_preference(Vec::new())
+        .build(server.clone());
+
+    unwrap_future(client.upload(&url, b"abc".to_vec())).expect("'upload' call failed");
+
+    assert!(server
+        .requests()
+        .iter()
+        .all(|r| !r.headers.contains_key("upload-checksum") && r.method != HttpMethod::Options));
+}
+
+#[test]
+fn should_request_server_info_for_checksums_once() {
+    let server = InMemoryServer::new();
+    let first_url = create_upload(&server, 3);
+    let second_url = create_upload(&server, 3);
+    let client = tus_client::Client::new(server.clone());
+
+    unwrap_future(client.upload(&first_url, b"abc".to_vec())).expect("'upload' call failed");
+    unwrap_future(client.upload(&second_url, b"def".to_vec())).expect("'upload' call failed");
+
+    let options: Vec<_> = server
+        .requests()
+        .into_iter()
+        .filter(|r| r.method == HttpMethod::Options)
+        .collect();
+    assert_eq!(1, options.len());
+    assert_eq!("/files", options[0].url);
+    assert!(server
+        .requests()
+        .iter()
+        .filter(|r| r.method == HttpMethod::Patch)
+        .all(|r| r.headers.contains_key("upload-checksum")));
+}
+
+#[test]
+fn should_reuse_server_info_of_creation_endpoint_when_resuming() {
+    let server = InMemoryServer::new();
+    server.inject_fault(Fault::on(HttpMethod::Patch, FaultKind::LostResponse));
+    let client = tus_client::Client::new(server.clone());
+
+    let result = unwrap_future(client.create_and_upload_with_chunk_size(
+        "/files/",
+        b"hello world".to_vec(),
+        Metadata::new(),
+        4,
+    ));
+    assert!(result.is_err());
+    let url = server
+        .requests()
+        .into_iter()
+        .find(|r| r.method == HttpMethod::Patch)
+        .unwrap()
+        .url;
+    unwrap_future(client.upload_with_chunk_size(&url, b"hello world".to_vec(), 4))
+        .expect("'upload_with_chunk_size' call failed");
+
+    assert_eq!(b"hello world", &server.upload(&url).unwrap().data[..]);
+    let options: Vec<_> = server
+        .requests()
+        .into_iter()
+        .filter(|r| r.method == HttpMethod::Options)
+        .map(|r| r.url)
+        .collect();
+    assert_eq!(vec!["/files/"], options);
+}
+
+#[test]
+fn should_report_progress_to_listener_of_builder() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 3);
+    let (sender, events) = progress_channel();
+    let client = ClientBuilder::new()
+        .progress_listener(sender)
+        .build(server.clone());
+
+    unwrap_future(client.upload(&url, b"abc".to_vec())).expect("'upload' call failed");
+    drop(client);
+
+    let events: Vec<ProgressEvent> = futures::executor::block_on(events.collect());
+    assert_eq!(
+        Some(&ProgressEvent::Completed { bytes_uploaded: 3 }),
+        events.last()
+    );
+}
+
+#[test]
+fn should_share_settings_between_clones() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 3);
+    let client = ClientBuilder::new()
+        .header("Authorization", "Bearer secret")
+        .build(server.clone());
+    let clone = client.clone();
+    drop(client);
+
+    unwrap_future(clone.upload(&url, b"abc".to_vec())).expect("'upload' call failed");
+
+    let last = server.requests().pop().unwrap();
+    assert_eq!("Bearer secret", last.headers["Authorization"]);
+}
+
+/// Accepts requests authorized with the token in `valid`, which is rotated after the first chunk.
+struct AuthServer {
+    server: InMemoryServer,
+    valid: Arc<Mutex<String>>,
+    rotate_after_patch: bool,
+}
+
+impl HttpHandler for AuthServer {
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        let authorized = req.headers.get("authorization") == Some(&*self.valid.lock().unwrap());
+        if !authorized {
+            return Ok(HttpResponse {
+                headers: HashMap::new(),
+                status_code: 401,
+            });
+        }
+
+        let is_patch = req.method == HttpMethod::Patch;
+        let response = self.server.handle_request(req).await;
+        if is_patch && self.rotate_after_patch {
+            *self.valid.lock().unwrap() = "Bearer rotated".to_owned();
+        }
+        response
+    }
+}
+
+/// Hands out its current token, and refreshes it to the token the server accepts.
+struct TestTokenProvider {
+    current: Mutex<String>,
+    server_token: Arc<Mutex<String>>,
+    refreshes: Arc<AtomicUsize>,
+}
+
+impl TokenProvider for TestTokenProvider {
+    fn authorization(&self) -> BoxFuture<'_, Result<String, Error>> {
+        Box::pin(futures::future::ready(Ok(self
+            .current
+            .lock()
+            .unwrap()
+            .clone())))
+    }
+
+    fn refresh(&self) -> BoxFuture<'_, Result<String, Error>> {
+        self.refreshes.fetch_add(1, Ordering::SeqCst);
+        let token = self.server_token.lock().unwrap().clone();
+        *self.current.lock().unwrap() = token.clone();
+        Box::pin(futures::future::ready(Ok(token)))
+    }
+}
+
+fn create_auth_client(
+    server: &InMemoryServer,
+    token: &str,
+    rotate_after_patch: bool,
+) -> (tus_client::Client<AuthServer>, Arc<AtomicUsize>) {
+    let valid = Arc::new(Mutex::new("Bearer initial".to_owned()));
+    let refreshes = Arc::new(AtomicUsize::new(0));
+    let client = ClientBuilder::new()
+        .chunk_size(256 * 1024)
+        .token_provider(TestTokenProvider {
+            current: Mutex::new(token.to_owned()),
+            server_token: valid.clone(),
+            refreshes: refreshes.clone(),
+        })
+        .build(AuthServer {
+            server: server.clone(),
+            valid,
+            rotate_after_patch,
+        });
+    (client, refreshes)
+}
+
+#[test]
+fn should_authorize_requests_with_token_provider() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 3);
+    let (client, refreshes) = create_auth_client(&server, "Bearer initial", false);
+
+    unwrap_future(client.upload(&url, b"abc".to_vec())).expect("'upload' call failed");
+
+    assert_eq!(0, refreshes.load(Ordering::SeqCst));
+    assert!(server
+        .requests()
+        .iter()
+        .skip(1)
+        .all(|r| r.headers["authorization"] == "Bearer initial"));
+}
+
+#[test]
+fn should_refresh_token_expiring_during_upload() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    let (client, refreshes) = create_auth_client(&server, "Bearer initial", true);
+
+    unwrap_future(client.upload(&url, &buffer)).expect("'upload' call failed");
+
+    assert_eq!(1, refreshes.load(Ordering::SeqCst));
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+}
+
+#[test]
+fn should_fail_when_refreshed_token_is_rejected() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 3);
+    let refreshes = Arc::new(AtomicUsize::new(0));
+    let client = ClientBuilder::new()
+        .token_provider(TestTokenProvider {
+            current: Mutex::new("Bearer expired".to_owned()),
+            server_token: Arc::new(Mutex::new("Bearer revoked".to_owned())),
+            refreshes: refreshes.clone(),
+        })
+        .build(AuthServer {
+            server: server.clone(),
+            valid: Arc::new(Mutex::new("Bearer initial".to_owned())),
+            rotate_after_patch: false,
+        });
+
+    match unwrap_future(client.get_info(&url)) {
+        Err(Error::Unauthorized) => {}
+        result => panic!("Expected 'Error::Unauthorized', got {:?}", result),
+    }
+    assert_eq!(1, refreshes.load(Ordering::SeqCst));
+}
+
+/// Uploads the first chunk it sees itself before passing it on, as if another client raced to upload it.
+struct RacingHandler {
+    server: InMemoryServer,
+    raced: AtomicBool,
+}
+
+impl HttpHandler for RacingHandler {
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        if req.method != HttpMethod::Patch || self.raced.swap(true, Ordering::SeqCst) {
+            return self.server.handle_request(req).await;
+        }
+
+        let HttpRequest {
+            method,
+            headers,
+            url,
+            body,
+        } = req;
+        let body = body.unwrap().into_vec().await?;
+        let request = || HttpRequest {
+            method,
+            headers: headers.clone(),
+            url: url.clone(),
+            body: Some(HttpBody::Bytes(&body)),
+        };
+        self.server.handle_request(request()).await?;
+        self.server.handle_request(request()).await
+    }
+}
+
+#[test]
+fn should_continue_from_server_offset_after_conflict() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    let client = tus_client::Client::new(RacingHandler {
+        server: server.clone(),
+        raced: AtomicBool::new(false),
+    });
+
+    let result = unwrap_future(client.upload_with_chunk_size(&url, &buffer, 256 * 1024))
+        .expect("'upload_with_chunk_size' call failed");
+
+    assert_eq!(buffer.len() as u64, result.bytes_uploaded);
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+    let offsets: Vec<_> = server
+        .requests()
+        .iter()
+        .filter(|r| r.method == HttpMethod::Patch)
+        .map(|r| r.headers["upload-offset"].clone())
+        .collect();
+    assert_eq!(vec!["0", "0", "262144", "524288"], offsets);
+}
+
+#[test]
+fn should_fail_after_too_many_consecutive_conflicts() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 4096);
+    fail_patches(&server, 4, FaultKind::Status(409));
+    let client = tus_client::Client::new(server.clone());
+
+    match unwrap_future(client.upload(&url, vec![0; 4096])) {
+        Err(Error::WrongUploadOffsetError) => {}
+        result => panic!("Expected 'Error::WrongUploadOffsetError', got {:?}", result),
+    }
+    let heads = server
+        .requests()
+        .iter()
+        .filter(|r| r.method == HttpMethod::Head)
+        .count();
+    // the first request for the offset, and one after each of the 3 resyncs
+    assert_eq!(4, heads);
+}
+
+#[test]
+fn should_not_resync_offset_when_disabled() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 4096);
+    fail_patches(&server, 1, FaultKind::Status(409));
+    let client = ClientBuilder::new()
+        .max_offset_resyncs(0)
+        .build(server.clone());
+
+    match unwrap_future(client.upload(&url, vec![0; 4096])) {
+        Err(Error::WrongUploadOffsetError) => {}
+        result => panic!("Expected 'Error::WrongUploadOffsetError', got {:?}", result),
+    }
+}
+
+fn assert_send<T: Send>(_: &T) {}
+
+#[test]
+fn should_create_send_futures() {
+    let client = tus_client::Client::new(InMemoryServer::new());
+    let (store, _) = create_temp_store();
+    let fingerprint = create_fingerprint(0);
+    let reader = || Cursor::new(Vec::<u8>::new());
+    let source = Vec::<u8>::new;
+
+    assert_send(&client.get_info("/files/1"));
+    assert_send(&client.get_server_info("/files"));
+    assert_send(&client.create("/files", 0));
+    assert_send(&client.delete("/files/1"));
+    assert_send(&client.upload("/files/1", source()));
+    assert_send(&client.upload_stream("/files/1", reader()));
+    assert_send(&client.create_and_upload("/files", source(), Metadata::new()));
+    assert_send(&client.upload_with_store(
+        "/files",
+        source(),
+        &store,
+        &fingerprint,
+        Metadata::new(),
+    ));
+    assert_send(&client.upload_parallel("/files", source(), 2, Metadata::new()));
+    let mut upload = client.begin_upload("/files/1", source());
+    assert_send(&upload.run());
+}
+
+#[test]
+fn should_upload_on_another_thread() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    let client = tus_client::Client::new(server.clone());
+
+    let upload = {
+        let buffer = buffer.clone();
+        async move { client.upload(&url, buffer).await }
+    };
+    let result = std::thread::spawn(move || futures::executor::block_on(upload))
+        .join()
+        .unwrap()
+        .expect("'upload' call failed");
+
+    assert_eq!(buffer, server.upload(&result.url).unwrap().data);
+}
+
+/// Only needs to compile: the futures of a `Client` are `Send` for any `SendHttpHandler`.
+fn assert_send_for_any_send_handler<H>(client: &tus_client::Client<H>)
+where
+    H: tus_client::http::SendHttpHandler,
+{
+    assert_send(&client.upload("/files/1", Vec::new()));
+    assert_send(&client.create_and_upload("/files", Vec::new(), Metadata::new()));
+}
+
+#[test]
+fn should_create_send_futures_with_generic_handler() {
+    assert_send_for_any_send_handler(&tus_client::Client::new(InMemoryServer::new()));
+}
+
+/// A handler which isn't `Send`, like handlers built on `Rc` or on the `fetch` API of a browser.
+struct LocalHandler(Rc<InMemoryServer>);
+
+impl HttpHandler for LocalHandler {
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        self.0.handle_request(req).await
+    }
+}
+
+#[test]
+fn should_upload_through_handler_which_is_not_send() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 11);
+    let client = tus_client::Client::new(LocalHandler(Rc::new(server.clone())));
+
+    unwrap_future(client.upload(&url, b"hello world".to_vec())).expect("'upload' call failed");
+
+    assert_eq!(b"hello world", &server.upload(&url).unwrap().data[..]);
+}
+
+fn select_handler(server: &InMemoryServer, with_headers: bool) -> Box<dyn DynHttpHandler> {
+    if with_headers {
+        Box::new(
+            server
+                .clone()
+                .layer(HeadersLayer::default().header("X-Request-Source", "tests")),
+        )
+    } else {
+        Box::new(server.clone())
+    }
+}
+
+#[test]
+fn should_upload_through_dyn_handler() {
+    for with_headers in [false, true] {
+        let server = InMemoryServer::new();
+        let client = tus_client::Client::new(select_handler(&server, with_headers));
+
+        let url = unwrap_future(client.create("/files", 11)).unwrap();
+        unwrap_future(client.upload(&url, b"hello world".to_vec())).unwrap();
+
+        assert_eq!(b"hello world", &server.upload(&url).unwrap().data[..]);
+        assert_eq!(
+            with_headers,
+            server
+                .requests()
+                .iter()
+                .all(|r| r.headers.contains_key("X-Request-Source"))
+        );
+    }
+}
+
+#[test]
+fn should_create_send_futures_with_dyn_handler() {
+    let client = tus_client::Client::new(select_handler(&InMemoryServer::new(), false));
+
+    assert_send(&client.upload("/files/1", Vec::new()));
+}
+
+/// Records the length of each streamed body before passing the request on.
+struct StreamRecorder {
+    server: InMemoryServer,
+    streamed: Arc<Mutex<Vec<u64>>>,
+}
+
+impl HttpHandler for StreamRecorder {
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        if let Some(HttpBody::Reader { len, .. }) = &req.body {
+            self.streamed.lock().unwrap().push(*len);
+        }
+        self.server.handle_request(req).await
+    }
+}
+
+#[test]
+fn should_stream_chunks_from_reader() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 11);
+    let streamed = Arc::new(Mutex::new(Vec::new()));
+    let client = tus_client::Client::new(StreamRecorder {
+        server: server.clone(),
+        streamed: streamed.clone(),
+    });
+
+    unwrap_future(client.upload_with_chunk_size(&url, b"hello world".to_vec(), 4))
+        .expect("'upload_with_chunk_size' call failed");
+
+    assert_eq!(vec![4, 4, 3], *streamed.lock().unwrap());
+    assert_eq!(b"hello world", &server.upload(&url).unwrap().data[..]);
+    // the server verifies the checksum which was calculated from the streamed chunks
+    assert!(server
+        .requests()
+        .iter()
+        .filter(|r| r.method == HttpMethod::Patch)
+        .all(|r| r.headers.contains_key("upload-checksum")));
+}
+
+/// Records when chunks are read from the source and sent, to check that they overlap.
+struct EventRecorder {
+    server: InMemoryServer,
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl HttpHandler for EventRecorder {
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        if req.method != HttpMethod::Patch {
+            return self.server.handle_request(req).await;
+        }
+
+        let offset = req.headers["upload-offset"].clone();
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("send {} started", offset));
+        // gives the read of the next chunk the chance to run while this one is sent
+        let mut yielded = false;
+        futures::future::poll_fn(|cx| {
+            if yielded {
+                return Poll::Ready(());
+            }
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        })
+        .await;
+        let response = self.server.handle_request(req).await;
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("send {} finished", offset));
+        response
+    }
+}
+
+struct RecordingSource {
+    data: Vec<u8>,
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl UploadSource for RecordingSource {
+    fn len(&self) -> std::io::Result<u64> {
+        UploadSource::len(&self.data)
+    }
+
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.events.lock().unwrap().push(format!("read {}", offset));
+        self.data.read_at(offset, buf).await
+    }
+}
+
+#[test]
+fn should_read_next_chunk_while_sending() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 11);
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let client = ClientBuilder::new().read_ahead(true).build(EventRecorder {
+        server: server.clone(),
+        events: events.clone(),
+    });
+    let source = RecordingSource {
+        data: b"hello world".to_vec(),
+        events: events.clone(),
+    };
+
+    futures::executor::block_on(client.upload_with_chunk_size(&url, source, 4))
+        .expect("'upload_with_chunk_size' call failed");
+
+    assert_eq!(b"hello world", &server.upload(&url).unwrap().data[..]);
+    assert_eq!(
+        vec![
+            "read 0",
+            "send 0 started",
+            "read 4",
+            "send 0 finished",
+            "send 4 started",
+            "read 8",
+            "send 4 finished",
+            "send 8 started",
+            "send 8 finished",
+        ],
+        *events.lock().unwrap()
+    );
+}
+
+/// Tells the source which chunks were handed to the server.
+struct ChunkNotifier {
+    server: InMemoryServer,
+    sent: Mutex<mpsc::Sender<u64>>,
+}
+
+impl HttpHandler for ChunkNotifier {
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        let offset = match req.method {
+            HttpMethod::Patch => req.headers["upload-offset"].parse().ok(),
+            _ => None,
+        };
+        let response = self.server.handle_request(req).await;
+        if let Some(offset) = offset {
+            self.sent.lock().unwrap().send(offset).unwrap();
+        }
+        response
+    }
+}
+
+/// Blocks the thread while reading a chunk, until the chunk before it was handed to the server.
+struct BlockingSource {
+    data: Vec<u8>,
+    sent: Mutex<mpsc::Receiver<u64>>,
+}
+
+impl UploadSource for BlockingSource {
+    fn len(&self) -> std::io::Result<u64> {
+        UploadSource::len(&self.data)
+    }
+
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        if offset > 0 {
+            let sent = self
+                .sent
+                .lock()
+                .unwrap()
+                .recv_timeout(Duration::from_secs(5))
+                .expect("the chunk was read before the previous one was sent");
+            assert!(sent < offset);
+        }
+        self.data.read_at(offset, buf).await
+    }
+}
+
+#[test]
+fn should_send_chunk_before_blocking_read_of_next_chunk() {
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, 11);
+    let (sent_tx, sent_rx) = mpsc::channel();
+    let client = ClientBuilder::new().read_ahead(true).build(ChunkNotifier {
+        server: server.clone(),
+        sent: Mutex::new(sent_tx),
+    });
+    let source = BlockingSource {
+        data: b"hello world".to_vec(),
+        sent: Mutex::new(sent_rx),
+    };
+
+    unwrap_future(client.upload_with_chunk_size(&url, source, 4))
+        .expect("'upload_with_chunk_size' call failed");
+
+    assert_eq!(b"hello world", &server.upload(&url).unwrap().data[..]);
+}
+
+#[test]
+fn should_discard_chunk_read_ahead_after_partial_write() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    server.inject_fault(Fault::on(HttpMethod::Patch, FaultKind::PartialWrite(1000)));
+    let client = ClientBuilder::new().read_ahead(true).build(server.clone());
+
+    unwrap_future(client.upload_with_chunk_size(&url, &buffer, 100 * 1024))
+        .expect("'upload_with_chunk_size' call failed");
+
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+}
+
+#[test]
+fn should_discard_chunk_read_ahead_after_failed_chunk() {
+    let buffer = create_temp_file();
+    let server = InMemoryServer::new();
+    let url = create_upload(&server, buffer.len());
+    fail_patches(
+        &server,
+        2,
+        FaultKind::HandlerError("connection reset".to_owned()),
+    );
+    let client = ClientBuilder::new()
+        .read_ahead(true)
+        .retry_policy(RetryPolicy::new())
+        .timer(ImmediateTimer)
+        .build(server.clone());
+
+    unwrap_future(client.upload_with_chunk_size(&url, &buffer, 100 * 1024))
+        .expect("'upload_with_chunk_size' call failed");
+
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+}
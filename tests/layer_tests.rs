@@ -0,0 +1,149 @@
+use futures::executor::block_on;
+use std::sync::{Arc, Mutex};
+use tus_client::http::{
+    BearerAuthLayer, HeadersLayer, HttpHandlerExt, HttpMethod, HttpRequest, HttpResponse, Layer,
+    LoggingLayer, SendHttpHandler, Stack,
+};
+use tus_client::testing::InMemoryServer;
+use tus_client::{Client, Error};
+
+/// Records its name for every request passing through it.
+#[derive(Clone, Default)]
+struct RecordLayer {
+    name: &'static str,
+    seen: Arc<Mutex<Vec<&'static str>>>,
+}
+
+struct Record<H> {
+    inner: H,
+    layer: RecordLayer,
+}
+
+impl<H> Layer<H> for RecordLayer
+where
+    H: SendHttpHandler,
+{
+    type Handler = Record<H>;
+
+    fn layer(&self, inner: H) -> Self::Handler {
+        Record {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+impl<H> SendHttpHandler for Record<H>
+where
+    H: SendHttpHandler,
+{
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        self.layer.seen.lock().unwrap().push(self.layer.name);
+        self.inner.handle_request(req).await
+    }
+}
+
+/// Turns every `404 Not Found` response into a `410 Gone` response.
+struct GoneLayer;
+
+struct Gone<H>(H);
+
+impl<H> Layer<H> for GoneLayer
+where
+    H: SendHttpHandler,
+{
+    type Handler = Gone<H>;
+
+    fn layer(&self, inner: H) -> Self::Handler {
+        Gone(inner)
+    }
+}
+
+impl<H> SendHttpHandler for Gone<H>
+where
+    H: SendHttpHandler,
+{
+    async fn handle_request<'a>(&self, req: HttpRequest<'a>) -> Result<HttpResponse, Error> {
+        let mut response = self.0.handle_request(req).await?;
+        if response.status_code == 404 {
+            response.status_code = 410;
+        }
+        Ok(response)
+    }
+}
+
+#[test]
+fn should_add_headers_to_every_request() {
+    let server = InMemoryServer::new();
+    let handler = server
+        .clone()
+        .layer(HeadersLayer::default().header("X-Request-Source", "tests"));
+    let client = Client::new(handler);
+
+    let url = block_on(client.create("/files", 3)).unwrap();
+    block_on(client.upload(&url, b"abc".to_vec())).unwrap();
+
+    assert!(server
+        .requests()
+        .iter()
+        .all(|r| r.headers["X-Request-Source"] == "tests"));
+}
+
+#[test]
+fn should_not_replace_headers_of_request() {
+    let server = InMemoryServer::new();
+    let handler = server
+        .clone()
+        .layer(HeadersLayer::default().header("Tus-Resumable", "0.2.2"));
+    let client = Client::new(handler);
+
+    block_on(client.create("/files", 3)).unwrap();
+
+    let post = server.requests().pop().unwrap();
+    assert_eq!(HttpMethod::Post, post.method);
+    assert_eq!("1.0.0", post.headers["tus-resumable"]);
+    assert!(!post.headers.contains_key("Tus-Resumable"));
+}
+
+#[test]
+fn should_authenticate_with_bearer_token() {
+    let server = InMemoryServer::new();
+    let client = Client::new(server.clone().layer(BearerAuthLayer::new("secret")));
+
+    block_on(client.create("/files", 3)).unwrap();
+
+    let post = server.requests().pop().unwrap();
+    assert_eq!("Bearer secret", post.headers["Authorization"]);
+}
+
+#[test]
+fn should_apply_outer_layer_first() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let inner = RecordLayer {
+        name: "inner",
+        seen: seen.clone(),
+    };
+    let outer = RecordLayer {
+        name: "outer",
+        seen: seen.clone(),
+    };
+    let client = Client::new(InMemoryServer::new().layer(Stack::new(inner, outer)));
+
+    block_on(client.create("/files", 3)).unwrap();
+
+    assert_eq!(vec!["outer", "inner"], *seen.lock().unwrap());
+}
+
+#[test]
+fn should_let_layers_change_responses() {
+    let client = Client::new(
+        InMemoryServer::new()
+            .layer(GoneLayer)
+            .layer(LoggingLayer::new()),
+    );
+
+    match block_on(client.get_info("/files/unknown")) {
+        Err(Error::Gone) => {}
+        result => panic!("Expected 'Error::Gone', got {:?}", result),
+    }
+}
@@ -0,0 +1,155 @@
+#![cfg(feature = "reqwest")]
+use std::collections::HashMap;
+use std::future::Future;
+use std::task::Poll;
+use tus_client::http::{HttpBody, HttpHandler, HttpMethod, HttpRequest};
+use tus_client::Error;
+
+fn unwrap_future<F>(fut: F) -> F::Output
+where
+    F: Future,
+{
+    let waker = futures::task::noop_waker();
+    let mut context = std::task::Context::from_waker(&waker);
+    let mut pin = Box::pin(fut);
+    let Poll::Ready(result) = Future::poll(pin.as_mut(), &mut context) else {
+        panic!("Future did not resolve");
+    };
+
+    result
+}
+
+fn create_request(headers: HashMap<String, String>) -> HttpRequest<'static> {
+    HttpRequest {
+        method: HttpMethod::Head,
+        headers,
+        url: String::from("http://localhost/files/1"),
+        body: None,
+    }
+}
+
+#[test]
+fn should_return_error_for_invalid_header_name() {
+    let mut headers = HashMap::new();
+    headers.insert("invalid header".to_owned(), "value".to_owned());
+
+    let result = unwrap_future(reqwest::Client::new().handle_request(create_request(headers)));
+
+    match result {
+        Err(Error::HttpHandlerError(_)) => {}
+        _ => panic!("Expected 'Error::HttpHandlerError'"),
+    }
+}
+
+#[test]
+fn should_return_error_for_invalid_header_value() {
+    let mut headers = HashMap::new();
+    headers.insert("upload-metadata".to_owned(), "line\nbreak".to_owned());
+
+    let result = unwrap_future(reqwest::Client::new().handle_request(create_request(headers)));
+
+    match result {
+        Err(Error::HttpHandlerError(_)) => {}
+        _ => panic!("Expected 'Error::HttpHandlerError'"),
+    }
+}
+
+#[cfg(feature = "reqwest-blocking")]
+#[test]
+fn should_send_request_with_blocking_client() {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/files/1", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 1024];
+        let len = stream.read(&mut request).unwrap();
+        stream
+            .write_all(
+                b"HTTP/1.1 204 No Content\r\nUpload-Offset: 1234\r\nTus-Resumable: 1.0.0\r\n\r\n",
+            )
+            .unwrap();
+        String::from_utf8_lossy(&request[..len]).to_lowercase()
+    });
+
+    let mut headers = HashMap::new();
+    headers.insert("tus-resumable".to_owned(), "1.0.0".to_owned());
+    let request = HttpRequest {
+        url,
+        ..create_request(headers)
+    };
+
+    let response = unwrap_future(reqwest::blocking::Client::new().handle_request(request))
+        .expect("'handle_request' call failed");
+
+    assert_eq!(204, response.status_code);
+    assert_eq!("1234", response.headers["upload-offset"]);
+    let request = server.join().unwrap();
+    assert!(request.starts_with("head /files/1 "));
+    assert!(request.contains("tus-resumable: 1.0.0"));
+}
+
+/// Accepts a single request, answers it with `response` and returns the request, including its body.
+fn serve_once(response: &'static [u8]) -> (String, std::thread::JoinHandle<Vec<u8>>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/files/1", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request = Vec::new();
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+                content_length = len.trim().parse().unwrap();
+            }
+            request.extend_from_slice(line.as_bytes());
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        request.extend_from_slice(&body);
+
+        reader.get_mut().write_all(response).unwrap();
+        request
+    });
+
+    (url, server)
+}
+
+#[test]
+fn should_stream_reader_body() {
+    let (url, server) =
+        serve_once(b"HTTP/1.1 204 No Content\r\nUpload-Offset: 11\r\nTus-Resumable: 1.0.0\r\n\r\n");
+    let mut reader = futures::io::Cursor::new(b"hello world".to_vec());
+    let request = HttpRequest {
+        method: HttpMethod::Patch,
+        headers: HashMap::new(),
+        url,
+        body: Some(HttpBody::Reader {
+            reader: &mut reader,
+            len: 11,
+        }),
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let response = runtime
+        .block_on(reqwest::Client::new().handle_request(request))
+        .expect("'handle_request' call failed");
+
+    assert_eq!(204, response.status_code);
+    let request = String::from_utf8(server.join().unwrap()).unwrap();
+    assert!(request.to_lowercase().contains("content-length: 11\r\n"));
+    assert!(request.ends_with("\r\n\r\nhello world"));
+}
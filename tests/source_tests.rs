@@ -0,0 +1,143 @@
+use bytes::Bytes;
+use futures::executor::block_on;
+use futures::io::Cursor;
+use std::io;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tus_client::testing::InMemoryServer;
+use tus_client::{Client, Error, Metadata, ReaderSource, SourceRange, UploadSource};
+
+fn read_at<S>(source: &S, offset: u64, len: usize) -> Vec<u8>
+where
+    S: UploadSource + ?Sized,
+{
+    let mut buf = vec![0; len];
+    let read = block_on(source.read_at(offset, &mut buf)).expect("'read_at' call failed");
+    buf.truncate(read);
+    buf
+}
+
+/// Claims to be longer than the data it holds, like a file which was truncated during the upload.
+struct TruncatedSource(Vec<u8>);
+
+impl UploadSource for TruncatedSource {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.0.len() as u64 * 2)
+    }
+
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read_at(offset, buf).await
+    }
+}
+
+#[test]
+fn should_read_in_memory_sources_at_offset() {
+    let vec = b"hello world".to_vec();
+    let bytes = Bytes::from_static(b"hello world");
+
+    assert_eq!(11, vec.len());
+    assert_eq!(11, UploadSource::len(&bytes).unwrap());
+    assert_eq!(b"world", &read_at(&vec, 6, 10)[..]);
+    assert_eq!(b"lo w", &read_at(&bytes, 3, 4)[..]);
+    assert!(read_at(&vec[..], 11, 4).is_empty());
+    assert!(read_at(&vec, 20, 4).is_empty());
+}
+
+#[test]
+fn should_limit_reads_to_range() {
+    let range = SourceRange::new(b"hello world".to_vec(), 2, 5);
+
+    assert_eq!(5, range.len().unwrap());
+    assert_eq!(b"llo w", &read_at(&range, 0, 11)[..]);
+    assert_eq!(b"o w", &read_at(&range, 2, 11)[..]);
+    assert!(read_at(&range, 5, 11).is_empty());
+}
+
+#[test]
+fn should_read_from_seekable_reader() {
+    let source = block_on(ReaderSource::new(Cursor::new(b"hello world".to_vec()))).unwrap();
+
+    assert_eq!(11, source.len().unwrap());
+    assert_eq!(b"world", &read_at(&source, 6, 5)[..]);
+    assert_eq!(b"hello", &read_at(&source, 0, 5)[..]);
+}
+
+#[test]
+fn should_upload_file() {
+    let path = std::env::temp_dir().join(format!("tus_source_{}", rand::random::<u64>()));
+    std::fs::File::create(&path)
+        .and_then(|mut file| file.write_all(b"hello world"))
+        .unwrap();
+    let file = std::fs::File::open(&path).unwrap();
+    let server = InMemoryServer::new();
+    let client = Client::new(server.clone());
+
+    let url = block_on(client.create("/files", 11)).unwrap();
+    block_on(client.upload_with_chunk_size(&url, &file, 4)).expect("'upload' call failed");
+
+    assert_eq!(b"hello world", &server.upload(&url).unwrap().data[..]);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn should_upload_parts_of_shared_source_in_parallel() {
+    let buffer: Vec<u8> = (0..(1024 * 763)).map(|_| rand::random::<u8>()).collect();
+    let source = Arc::new(Bytes::from(buffer.clone()));
+    let server = InMemoryServer::new();
+    let client = Client::new(server.clone());
+
+    let result = block_on(client.upload_parallel("/files", source.clone(), 4, Metadata::new()))
+        .expect("'upload_parallel' call failed");
+
+    assert_eq!(buffer, server.upload(&result.url).unwrap().data);
+    assert_eq!(1, Arc::strong_count(&source));
+}
+
+#[test]
+fn should_fail_when_source_ends_early() {
+    let server = InMemoryServer::new();
+    let client = Client::new(server.clone());
+    let url = block_on(client.create("/files", 22)).unwrap();
+
+    match block_on(client.upload(&url, TruncatedSource(b"hello world".to_vec()))) {
+        Err(Error::IoError(err)) => assert_eq!(io::ErrorKind::UnexpectedEof, err.kind()),
+        result => panic!("Expected 'Error::IoError', got {:?}", result),
+    }
+    assert!(server.upload(&url).unwrap().data.is_empty());
+}
+
+/// Records the size of the largest read from the source.
+struct LargestReadSource {
+    data: Vec<u8>,
+    largest_read: AtomicUsize,
+}
+
+impl UploadSource for LargestReadSource {
+    fn len(&self) -> io::Result<u64> {
+        UploadSource::len(&self.data)
+    }
+
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.largest_read.fetch_max(buf.len(), Ordering::SeqCst);
+        self.data.read_at(offset, buf).await
+    }
+}
+
+#[test]
+fn should_read_large_chunk_in_bounded_reads() {
+    let buffer: Vec<u8> = (0..(1024 * 763)).map(|_| rand::random::<u8>()).collect();
+    let source = LargestReadSource {
+        data: buffer.clone(),
+        largest_read: AtomicUsize::new(0),
+    };
+    let server = InMemoryServer::new();
+    let client = Client::new(server.clone());
+
+    let url = block_on(client.create("/files", buffer.len() as u64)).unwrap();
+    block_on(client.upload_with_chunk_size(&url, &source, buffer.len()))
+        .expect("'upload' call failed");
+
+    assert_eq!(buffer, server.upload(&url).unwrap().data);
+    assert!(source.largest_read.load(Ordering::SeqCst) <= 64 * 1024);
+}
@@ -0,0 +1,195 @@
+use futures::executor::block_on;
+use std::collections::HashMap;
+use std::time::Duration;
+use tus_client::http::{HttpBody, HttpHandler, HttpMethod, HttpRequest};
+use tus_client::testing::{Fault, FaultKind, InMemoryServer};
+use tus_client::{Client, ClientBuilder, Error, Metadata, RetryPolicy, TusExtension};
+
+fn patch_request<'a>(url: &str, offset: usize, body: &'a [u8]) -> HttpRequest<'a> {
+    let mut headers = HashMap::new();
+    headers.insert("Tus-Resumable".to_owned(), "1.0.0".to_owned());
+    headers.insert("upload-offset".to_owned(), offset.to_string());
+    headers.insert(
+        "content-type".to_owned(),
+        "application/offset+octet-stream".to_owned(),
+    );
+
+    HttpRequest {
+        method: HttpMethod::Patch,
+        headers,
+        url: url.to_owned(),
+        body: Some(HttpBody::Bytes(body)),
+    }
+}
+
+#[test]
+fn should_reject_patch_at_wrong_offset() {
+    let server = InMemoryServer::new();
+    let client = Client::new(server.clone());
+    let url = block_on(client.create("/files", 10)).unwrap();
+
+    let response = block_on(server.handle_request(patch_request(&url, 3, b"abc"))).unwrap();
+
+    assert_eq!(409, response.status_code);
+    assert!(server.upload(&url).unwrap().data.is_empty());
+}
+
+#[test]
+fn should_report_unknown_upload_as_not_found() {
+    let server = InMemoryServer::new();
+    let client = Client::new(server.clone());
+
+    match block_on(client.get_info("/files/unknown")) {
+        Err(Error::NotFoundError) => {}
+        result => panic!("Expected 'Error::NotFoundError', got {:?}", result),
+    }
+}
+
+#[test]
+fn should_remove_terminated_upload() {
+    let server = InMemoryServer::new();
+    let client = Client::new(server.clone());
+    let url = block_on(client.create("/files", 10)).unwrap();
+
+    block_on(client.delete(&url)).expect("'delete' call failed");
+
+    assert!(server.upload(&url).is_none());
+    assert!(block_on(client.get_info(&url)).is_err());
+}
+
+#[test]
+fn should_not_terminate_without_termination_extension() {
+    let server = InMemoryServer::with_extensions(vec![TusExtension::Creation]);
+    let client = Client::new(server.clone());
+    let url = block_on(client.create("/files", 10)).unwrap();
+
+    match block_on(client.delete(&url)) {
+        Err(Error::UnexpectedStatusCode(405)) => {}
+        result => panic!("Expected status code 405, got {:?}", result),
+    }
+    assert!(server.upload(&url).is_some());
+}
+
+#[test]
+fn should_respond_gone_for_expired_upload() {
+    let server = InMemoryServer::new().expires_after(Duration::from_secs(60));
+    let client = Client::new(server.clone());
+    let url = block_on(client.create("/files", 10)).unwrap();
+    assert!(server.upload(&url).unwrap().expires.is_some());
+
+    server.expire(&url);
+
+    let response = block_on(server.handle_request(patch_request(&url, 0, b"abc"))).unwrap();
+    assert_eq!(410, response.status_code);
+}
+
+#[test]
+fn should_reject_upload_larger_than_max_size() {
+    let server = InMemoryServer::new().max_size(100);
+    let client = Client::new(server);
+
+    match block_on(client.create("/files", 101)) {
+        Err(Error::FileTooLarge) => {}
+        result => panic!("Expected 'Error::FileTooLarge', got {:?}", result),
+    }
+}
+
+#[test]
+fn should_complete_deferred_length_upload() {
+    let server = InMemoryServer::new();
+    let client = Client::new(server.clone());
+    let url = block_on(client.create_with_deferred_length("/files", Metadata::new())).unwrap();
+    assert_eq!(None, server.upload(&url).unwrap().length);
+
+    block_on(client.upload_stream(&url, &b"hello world"[..])).unwrap();
+
+    let upload = server.upload(&url).unwrap();
+    assert_eq!(Some(11), upload.length);
+    assert!(upload.is_complete());
+}
+
+#[test]
+fn should_reject_chunk_with_wrong_checksum() {
+    let server = InMemoryServer::new();
+    let client = Client::new(server.clone());
+    let url = block_on(client.create("/files", 3)).unwrap();
+
+    let mut req = patch_request(&url, 0, b"abc");
+    req.headers.insert(
+        "upload-checksum".to_owned(),
+        "sha1 Kq5sNclPz7QV2+lfQIuc6R7oRu0=".to_owned(),
+    );
+    let response = block_on(server.handle_request(req)).unwrap();
+
+    assert_eq!(460, response.status_code);
+    assert!(server.upload(&url).unwrap().data.is_empty());
+}
+
+#[test]
+fn should_concatenate_partial_uploads() {
+    let server = InMemoryServer::new();
+    let client = Client::new(server.clone());
+    let first = block_on(client.create_partial("/files", 5)).unwrap();
+    let second = block_on(client.create_partial("/files", 6)).unwrap();
+    block_on(client.upload(&first, b"hello".to_vec())).unwrap();
+    block_on(client.upload(&second, b" world".to_vec())).unwrap();
+
+    let url =
+        block_on(client.concatenate("/files", &[first.clone(), second.clone()], Metadata::new()))
+            .unwrap();
+
+    let upload = server.upload(&url).unwrap();
+    assert_eq!(b"hello world", &upload.data[..]);
+    assert_eq!(Some(vec![first, second]), upload.concatenated_from);
+}
+
+#[test]
+fn should_apply_faults_in_order() {
+    let server = InMemoryServer::new();
+    let client = Client::new(server.clone());
+    server.inject_fault(Fault::next(FaultKind::Status(503)));
+    server.inject_fault(Fault::next(FaultKind::HandlerError("reset".to_owned())));
+
+    match block_on(client.create("/files", 10)) {
+        Err(Error::UnexpectedStatusCode(503)) => {}
+        result => panic!("Expected status code 503, got {:?}", result),
+    }
+    match block_on(client.create("/files", 10)) {
+        Err(Error::HttpHandlerError(message)) => assert_eq!("reset", message),
+        result => panic!("Expected 'Error::HttpHandlerError', got {:?}", result),
+    }
+    assert!(block_on(client.create("/files", 10)).is_ok());
+}
+
+#[test]
+fn should_store_request_of_lost_response() {
+    let server = InMemoryServer::new();
+    let client = Client::new(server.clone());
+    let url = block_on(client.create("/files", 3)).unwrap();
+    server.inject_fault(Fault::on(HttpMethod::Patch, FaultKind::LostResponse));
+
+    assert!(block_on(client.upload(&url, b"abc".to_vec())).is_err());
+    assert_eq!(b"abc", &server.upload(&url).unwrap().data[..]);
+}
+
+#[test]
+fn should_resume_after_partial_write() {
+    // without checksums, so the truncated body is stored instead of rejected
+    let server = InMemoryServer::with_extensions(vec![TusExtension::Creation]);
+    let client = ClientBuilder::new()
+        .retry_policy(RetryPolicy::new().base_delay(Duration::ZERO))
+        .build(server.clone());
+    let url = block_on(client.create("/files", 11)).unwrap();
+    server.inject_fault(Fault::on(HttpMethod::Patch, FaultKind::PartialWrite(4)));
+
+    block_on(client.upload(&url, b"hello world".to_vec())).unwrap();
+
+    assert_eq!(b"hello world", &server.upload(&url).unwrap().data[..]);
+    let patches: Vec<usize> = server
+        .requests()
+        .iter()
+        .filter(|r| r.method == HttpMethod::Patch)
+        .map(|r| r.body_len)
+        .collect();
+    assert_eq!(vec![11, 7], patches);
+}
@@ -0,0 +1,177 @@
+#![cfg(feature = "tower")]
+use bytes::Bytes;
+use futures::executor::block_on;
+use futures::future::BoxFuture;
+use http::{Request, Response};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::task::{Context, Poll};
+use tower_service::Service;
+use tus_client::http::{HttpBody, HttpHandler, HttpMethod, HttpRequest, ServiceHandler};
+use tus_client::testing::InMemoryServer;
+use tus_client::{Client, Error};
+
+/// Serves `InMemoryServer` as a tower service, converting the `http` types back.
+#[derive(Clone)]
+struct InMemoryService(InMemoryServer);
+
+impl Service<Request<Bytes>> for InMemoryService {
+    type Response = Response<()>;
+    type Error = String;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Bytes>) -> Self::Future {
+        let server = self.0.clone();
+        Box::pin(async move {
+            let method = match *req.method() {
+                http::Method::HEAD => HttpMethod::Head,
+                http::Method::PATCH => HttpMethod::Patch,
+                http::Method::OPTIONS => HttpMethod::Options,
+                http::Method::POST => HttpMethod::Post,
+                http::Method::DELETE => HttpMethod::Delete,
+                ref method => return Err(format!("Unsupported method {}", method)),
+            };
+            let headers = req
+                .headers()
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_str().unwrap().to_owned()))
+                .collect();
+            let body = req.body();
+            let response = server
+                .handle_request(HttpRequest {
+                    method,
+                    headers,
+                    url: req.uri().to_string(),
+                    body: if body.is_empty() {
+                        None
+                    } else {
+                        Some(HttpBody::Bytes(body))
+                    },
+                })
+                .await
+                .map_err(|err| err.to_string())?;
+
+            let mut builder = Response::builder().status(response.status_code as u16);
+            for (key, value) in response.headers {
+                builder = builder.header(key, value);
+            }
+            Ok(builder.body(()).unwrap())
+        })
+    }
+}
+
+/// Forwards to `InMemoryService`, but isn't `Sync`, like `tower::util::BoxCloneService`.
+#[derive(Clone)]
+struct UnsyncService {
+    inner: InMemoryService,
+    _unsync: PhantomData<Cell<()>>,
+}
+
+impl Service<Request<Bytes>> for UnsyncService {
+    type Response = Response<()>;
+    type Error = String;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Bytes>) -> Self::Future {
+        self.inner.call(req)
+    }
+}
+
+/// Fails every request.
+#[derive(Clone)]
+struct FailingService;
+
+impl Service<Request<Bytes>> for FailingService {
+    type Response = Response<()>;
+    type Error = std::io::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _req: Request<Bytes>) -> Self::Future {
+        Box::pin(async { Err(std::io::Error::other("connection refused")) })
+    }
+}
+
+#[test]
+fn should_upload_through_service() {
+    let server = InMemoryServer::new();
+    let client = Client::new(ServiceHandler::new(InMemoryService(server.clone())));
+
+    let url = block_on(client.create("/files", 11)).unwrap();
+    block_on(client.upload(&url, b"hello world".to_vec())).unwrap();
+
+    assert_eq!(b"hello world", &server.upload(&url).unwrap().data[..]);
+}
+
+fn assert_send<T: Send>(value: T) -> T {
+    value
+}
+
+#[test]
+fn should_upload_through_service_which_is_not_sync() {
+    let server = InMemoryServer::new();
+    let client = assert_send(Client::new(ServiceHandler::new(UnsyncService {
+        inner: InMemoryService(server.clone()),
+        _unsync: PhantomData,
+    })));
+
+    let url = block_on(client.create("/files", 11)).unwrap();
+    block_on(assert_send(client.upload(&url, b"hello world".to_vec()))).unwrap();
+
+    assert_eq!(b"hello world", &server.upload(&url).unwrap().data[..]);
+}
+
+#[test]
+fn should_convert_response_headers() {
+    let server = InMemoryServer::new();
+    let handler = ServiceHandler::new(InMemoryService(server));
+
+    let response = block_on(handler.handle_request(HttpRequest {
+        method: HttpMethod::Options,
+        headers: HashMap::new(),
+        url: "/files".to_owned(),
+        body: None,
+    }))
+    .unwrap();
+
+    assert_eq!(204, response.status_code);
+    assert_eq!("1.0.0", response.headers["tus-version"]);
+}
+
+#[test]
+fn should_report_service_error_as_handler_error() {
+    let client = Client::new(ServiceHandler::new(FailingService));
+
+    match block_on(client.get_info("/files/1")) {
+        Err(Error::HttpHandlerError(message)) => assert_eq!("connection refused", message),
+        result => panic!("Expected 'Error::HttpHandlerError', got {:?}", result),
+    }
+}
+
+#[test]
+fn should_reject_invalid_header_name() {
+    let handler = ServiceHandler::new(FailingService);
+    let mut headers = HashMap::new();
+    headers.insert("invalid header".to_owned(), "value".to_owned());
+
+    let result = block_on(handler.handle_request(HttpRequest {
+        method: HttpMethod::Head,
+        headers,
+        url: "/files/1".to_owned(),
+        body: None,
+    }));
+
+    assert!(matches!(result, Err(Error::HttpHandlerError(_))));
+}